@@ -0,0 +1,137 @@
+//! Example how to use `egui_glium` with multiple viewports, each in its own native window.
+
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use egui::{ViewportBuilder, ViewportId};
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::WindowId,
+};
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+
+    let viewports = egui_glium::EguiGliumViewports::new(
+        egui::Context::default(),
+        ViewportBuilder::default()
+            .with_title("egui_glium multiple viewports example")
+            .with_inner_size([800.0, 600.0]),
+    );
+
+    let mut app = App {
+        viewports,
+        show_immediate_viewport: false,
+        show_deferred_viewport: Arc::new(AtomicBool::new(false)),
+    };
+
+    let result = event_loop.run_app(&mut app);
+    result.unwrap()
+}
+
+struct App {
+    viewports: egui_glium::EguiGliumViewports,
+    show_immediate_viewport: bool,
+
+    /// Deferred viewports run on their own, so their state must be shareable.
+    show_deferred_viewport: Arc<AtomicBool>,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        self.viewports.resumed(event_loop);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
+        let is_root = self.viewports.viewport_id(id) == Some(ViewportId::ROOT);
+
+        match &event {
+            WindowEvent::Destroyed if is_root => event_loop.exit(),
+            WindowEvent::RedrawRequested => {
+                let show_immediate_viewport = &mut self.show_immediate_viewport;
+                let show_deferred_viewport = &self.show_deferred_viewport;
+                self.viewports.run_and_paint(event_loop, id, |egui_ctx| {
+                    root_ui(egui_ctx, show_immediate_viewport, show_deferred_viewport);
+                });
+
+                if self.viewports.should_close() {
+                    event_loop.exit();
+                }
+            }
+            _ => {}
+        }
+
+        // Redraws egui asks for are requested for us:
+        let _ = self.viewports.on_window_event(id, &event);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.viewports.about_to_wait(event_loop);
+    }
+}
+
+fn root_ui(
+    egui_ctx: &egui::Context,
+    show_immediate_viewport: &mut bool,
+    show_deferred_viewport: &Arc<AtomicBool>,
+) {
+    egui::CentralPanel::default().show(egui_ctx, |ui| {
+        ui.heading("Hello from the root viewport!");
+        ui.checkbox(show_immediate_viewport, "Show immediate viewport");
+
+        let mut show_deferred = show_deferred_viewport.load(Ordering::Relaxed);
+        if ui
+            .checkbox(&mut show_deferred, "Show deferred viewport")
+            .changed()
+        {
+            show_deferred_viewport.store(show_deferred, Ordering::Relaxed);
+        }
+    });
+
+    if *show_immediate_viewport {
+        egui_ctx.show_viewport_immediate(
+            ViewportId::from_hash_of("immediate_viewport"),
+            ViewportBuilder::default()
+                .with_title("Immediate viewport")
+                .with_inner_size([300.0, 200.0]),
+            |egui_ctx, _class| {
+                egui::CentralPanel::default().show(egui_ctx, |ui| {
+                    ui.label("Hello from the immediate viewport!");
+                    ui.label("It can borrow state from the parent.");
+                });
+
+                if egui_ctx.input(|i| i.viewport().close_requested()) {
+                    *show_immediate_viewport = false;
+                }
+            },
+        );
+    }
+
+    if show_deferred_viewport.load(Ordering::Relaxed) {
+        let show_deferred_viewport = show_deferred_viewport.clone();
+        egui_ctx.show_viewport_deferred(
+            ViewportId::from_hash_of("deferred_viewport"),
+            ViewportBuilder::default()
+                .with_title("Deferred viewport")
+                .with_inner_size([300.0, 200.0]),
+            move |egui_ctx, _class| {
+                egui::CentralPanel::default().show(egui_ctx, |ui| {
+                    ui.label("Hello from the deferred viewport!");
+                    ui.label("It is repainted on its own.");
+                });
+
+                if egui_ctx.input(|i| i.viewport().close_requested()) {
+                    show_deferred_viewport.store(false, Ordering::Relaxed);
+                    // The root needs to notice that the viewport is gone:
+                    egui_ctx.request_repaint_of(ViewportId::ROOT);
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use egui_winit::ActionRequested;
+
+use crate::{Error, Painter};
+
+/// Things [`egui_winit::process_viewport_commands`] wants us to do after the next paint.
+///
+/// Shared by [`crate::EguiGlium`] and [`crate::EguiGliumViewports`].
+#[derive(Default)]
+pub(crate) struct ActionsRequested(Vec<ActionRequested>);
+
+impl ActionsRequested {
+    /// Apply `commands` to `window`, and remember the actions they leave to us.
+    pub fn process_viewport_commands(
+        &mut self,
+        egui_ctx: &egui::Context,
+        info: &mut egui::ViewportInfo,
+        commands: impl IntoIterator<Item = egui::ViewportCommand>,
+        window: &winit::window::Window,
+    ) {
+        egui_winit::process_viewport_commands(egui_ctx, info, commands, window, &mut self.0);
+    }
+
+    /// Turn cut, copy and paste into input for the next frame.
+    ///
+    /// Screenshots are kept until [`Self::handle_screenshots`].
+    pub fn handle_clipboard(&mut self, egui_winit: &mut egui_winit::State) {
+        self.0.retain(|action| {
+            let event = match action {
+                ActionRequested::Screenshot(_) => return true,
+                ActionRequested::Cut => egui::Event::Cut,
+                ActionRequested::Copy => egui::Event::Copy,
+                ActionRequested::Paste => {
+                    let Some(text) = egui_winit.clipboard_text() else {
+                        return false;
+                    };
+                    egui::Event::Paste(text)
+                }
+            };
+            egui_winit.egui_input_mut().events.push(event);
+            false
+        });
+    }
+
+    /// If a screenshot was requested, read back `target` right after painting it,
+    /// and deliver it as an [`egui::Event::Screenshot`] on the next frame.
+    ///
+    /// Returns `true` if there was a screenshot, so the caller should repaint soon.
+    pub fn handle_screenshots<T: glium::Surface>(
+        &mut self,
+        egui_winit: &mut egui_winit::State,
+        viewport_id: egui::ViewportId,
+        painter: &Painter,
+        facade: &dyn glium::backend::Facade,
+        target: &T,
+    ) -> Result<bool, Error> {
+        let is_screenshot =
+            |action: &ActionRequested| matches!(action, ActionRequested::Screenshot(_));
+        if !self.0.iter().any(is_screenshot) {
+            return Ok(false);
+        }
+
+        let image = std::sync::Arc::new(painter.read_screen_rgba(facade, target)?);
+        self.0.retain(|action| {
+            let ActionRequested::Screenshot(user_data) = action else {
+                return true;
+            };
+            egui_winit
+                .egui_input_mut()
+                .events
+                .push(egui::Event::Screenshot {
+                    viewport_id,
+                    user_data: user_data.clone(),
+                    image: image.clone(),
+                });
+            false
+        });
+        Ok(true)
+    }
+}
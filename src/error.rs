@@ -0,0 +1,92 @@
+/// Errors that can happen when setting up or painting with [`crate::Painter`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// None of our shaders can run on this OpenGL version.
+    UnsupportedGlslVersion(glium::Version),
+
+    /// The shader failed to compile or link.
+    ProgramCreation(glium::ProgramCreationError),
+
+    /// Failed to allocate the vertex buffer.
+    VertexBufferCreation(glium::vertex::BufferCreationError),
+
+    /// Failed to allocate the index buffer.
+    IndexBufferCreation(glium::index::BufferCreationError),
+
+    /// Failed to allocate a texture.
+    TextureCreation(glium::texture::TextureCreationError),
+
+    /// Failed to create a framebuffer to paint into a texture, or to read one back.
+    FramebufferValidation(glium::framebuffer::ValidationError),
+
+    /// A draw call failed.
+    Draw(glium::DrawError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedGlslVersion(version) => write!(
+                f,
+                "Failed to find a compatible shader for OpenGL version {version:?}"
+            ),
+            Self::ProgramCreation(err) => write!(f, "Failed to compile shader: {err}"),
+            Self::VertexBufferCreation(err) => write!(f, "Failed to create vertex buffer: {err}"),
+            Self::IndexBufferCreation(err) => write!(f, "Failed to create index buffer: {err}"),
+            Self::TextureCreation(err) => write!(f, "Failed to create texture: {err}"),
+            Self::FramebufferValidation(err) => write!(f, "Failed to create framebuffer: {err}"),
+            Self::Draw(err) => write!(f, "Failed to draw: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::UnsupportedGlslVersion(_) => None,
+            Self::ProgramCreation(err) => Some(err),
+            Self::VertexBufferCreation(err) => Some(err),
+            Self::IndexBufferCreation(err) => Some(err),
+            Self::TextureCreation(err) => Some(err),
+            Self::FramebufferValidation(err) => Some(err),
+            Self::Draw(err) => Some(err),
+        }
+    }
+}
+
+impl From<glium::ProgramCreationError> for Error {
+    fn from(err: glium::ProgramCreationError) -> Self {
+        Self::ProgramCreation(err)
+    }
+}
+
+impl From<glium::vertex::BufferCreationError> for Error {
+    fn from(err: glium::vertex::BufferCreationError) -> Self {
+        Self::VertexBufferCreation(err)
+    }
+}
+
+impl From<glium::index::BufferCreationError> for Error {
+    fn from(err: glium::index::BufferCreationError) -> Self {
+        Self::IndexBufferCreation(err)
+    }
+}
+
+impl From<glium::texture::TextureCreationError> for Error {
+    fn from(err: glium::texture::TextureCreationError) -> Self {
+        Self::TextureCreation(err)
+    }
+}
+
+impl From<glium::framebuffer::ValidationError> for Error {
+    fn from(err: glium::framebuffer::ValidationError) -> Self {
+        Self::FramebufferValidation(err)
+    }
+}
+
+impl From<glium::DrawError> for Error {
+    fn from(err: glium::DrawError) -> Self {
+        Self::Draw(err)
+    }
+}
@@ -1,6 +1,7 @@
 //! [`egui`] bindings for [`glium`](https://github.com/glium/glium).
 //!
 //! The main type you want to use is [`EguiGlium`].
+//! For a simple app with a single window, [`runner`] can own the event loop for you.
 //!
 //! If you are writing an app, you may want to look at [`eframe`](https://docs.rs/eframe) instead.
 //!
@@ -12,9 +13,22 @@
 #![allow(clippy::manual_range_contains)]
 #![forbid(unsafe_code)]
 
+mod actions;
+mod error;
+mod native_texture;
 mod painter;
-use glium::glutin::surface::WindowSurface;
-pub use painter::Painter;
+#[cfg(feature = "persistence")]
+mod persistence;
+pub mod runner;
+mod snapshot;
+mod viewports;
+
+pub use error::Error;
+pub use native_texture::{HdrOptions, NativeTexture, NativeTextureHandle, ToneMapping};
+pub use painter::{CallbackContext, CallbackFn, CallbackSurface, Painter, PainterStats};
+pub use snapshot::{snapshot, snapshot_with_painter};
+pub use viewports::EguiGliumViewports;
+
 use raw_window_handle::HasDisplayHandle;
 
 pub use egui_winit;
@@ -27,85 +41,649 @@ pub struct EguiGlium {
     pub egui_winit: egui_winit::State,
     pub painter: crate::Painter,
 
+    viewport_id: egui::ViewportId,
     shapes: Vec<egui::epaint::ClippedShape>,
     textures_delta: egui::TexturesDelta,
+
+    /// Refreshed from the window each frame, and handed to egui.
+    viewport_info: egui::ViewportInfo,
+
+    actions_requested: actions::ActionsRequested,
+
+    /// Set when egui asked to close the viewport and nobody canceled it.
+    should_close: bool,
+
+    /// How long until egui wants to run again, as of the last call to [`Self::run`].
+    repaint_delay: std::time::Duration,
+
+    /// When egui wants to run again, or `None` if it doesn't.
+    repaint_at: Option<std::time::Instant>,
+
+    /// Only for the root viewport.
+    #[cfg(feature = "persistence")]
+    persistence: Option<persistence::Persistence>,
 }
 
 impl EguiGlium {
+    /// Panics if the painter could not be created. See [`Self::try_new`].
     pub fn new(
         viewport_id: egui::ViewportId,
-        display: &glium::Display<WindowSurface>,
+        facade: &dyn glium::backend::Facade,
         window: &winit::window::Window,
         event_loop: &dyn HasDisplayHandle,
     ) -> Self {
-        let painter = crate::Painter::new(display);
+        Self::try_new(viewport_id, facade, window, event_loop).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`Self::new`], but returns an error if the painter could not be created.
+    pub fn try_new(
+        viewport_id: egui::ViewportId,
+        facade: &dyn glium::backend::Facade,
+        window: &winit::window::Window,
+        event_loop: &dyn HasDisplayHandle,
+    ) -> Result<Self, Error> {
+        Self::builder()
+            .with_viewport_id(viewport_id)
+            .build(facade, window, event_loop)
+    }
+
+    /// For when the defaults of [`Self::new`] don't fit, e.g. to share an [`egui::Context`]
+    /// between several windows.
+    pub fn builder<'a>() -> EguiGliumBuilder<'a> {
+        EguiGliumBuilder::default()
+    }
+
+    fn from_builder(
+        builder: EguiGliumBuilder<'_>,
+        facade: &dyn glium::backend::Facade,
+        window: &winit::window::Window,
+        event_loop: &dyn HasDisplayHandle,
+    ) -> Result<Self, Error> {
+        let EguiGliumBuilder {
+            egui_ctx,
+            viewport_id,
+            theme,
+            max_texture_side,
+            painter,
+            #[cfg(feature = "persistence")]
+            app_id,
+            #[cfg(feature = "persistence")]
+            autosave_interval,
+            #[cfg(feature = "accesskit")]
+            init_accesskit,
+            _lifetime,
+        } = builder;
+
+        let painter = match painter {
+            Some(painter) => painter,
+            None => crate::Painter::try_new(facade)?,
+        };
+        let max_texture_side = max_texture_side.map_or(painter.max_texture_side(), |side| {
+            side.min(painter.max_texture_side())
+        });
 
         let pixels_per_point = window.scale_factor() as f32;
-        let egui_winit = egui_winit::State::new(
-            Default::default(),
+        #[allow(unused_mut)]
+        let mut egui_winit = egui_winit::State::new(
+            egui_ctx,
             viewport_id,
             event_loop,
             Some(pixels_per_point),
-            None,
-            Some(painter.max_texture_side()),
+            theme,
+            Some(max_texture_side),
         );
 
-        Self {
+        #[cfg(feature = "accesskit")]
+        if let Some(init_accesskit) = init_accesskit {
+            init_accesskit(&mut egui_winit, window);
+        }
+
+        #[cfg(feature = "persistence")]
+        let persistence = (viewport_id == egui::ViewportId::ROOT)
+            .then(|| app_id.or_else(default_app_id))
+            .flatten()
+            .map(|app_id| {
+                let mut persistence = persistence::Persistence::new(
+                    egui_winit.egui_ctx().clone(),
+                    &app_id,
+                    autosave_interval.unwrap_or(persistence::DEFAULT_AUTOSAVE_INTERVAL),
+                );
+                persistence.restore(window);
+                persistence
+            });
+
+        let mut viewport_info = egui::ViewportInfo::default();
+        egui_winit::update_viewport_info(&mut viewport_info, egui_winit.egui_ctx(), window, true);
+
+        Ok(Self {
             egui_winit,
             painter,
+            viewport_id,
             shapes: Default::default(),
             textures_delta: Default::default(),
-        }
+            viewport_info,
+            actions_requested: Default::default(),
+            should_close: false,
+            repaint_delay: std::time::Duration::MAX,
+            repaint_at: None,
+            #[cfg(feature = "persistence")]
+            persistence,
+        })
     }
 
     pub fn egui_ctx(&self) -> &egui::Context {
         self.egui_winit.egui_ctx()
     }
 
+    /// Forward a window event to egui.
+    ///
+    /// [`winit::event::WindowEvent::CloseRequested`] is reported to egui as
+    /// [`egui::ViewportEvent::Close`], so the app can cancel it with
+    /// [`egui::ViewportCommand::CancelClose`]. Check [`Self::should_close`] after [`Self::run`].
     pub fn on_event(
         &mut self,
         window: &winit::window::Window,
         event: &winit::event::WindowEvent,
     ) -> EventResponse {
-        self.egui_winit.on_window_event(window, event)
+        let mut event_response = self.egui_winit.on_window_event(window, event);
+        if matches!(event, winit::event::WindowEvent::CloseRequested) {
+            self.viewport_info.events.push(egui::ViewportEvent::Close);
+            event_response.repaint = true;
+        }
+        event_response
+    }
+
+    /// Forward a raw device event to egui.
+    ///
+    /// [`winit::event::DeviceEvent::MouseMotion`] becomes an [`egui::Event::MouseMoved`],
+    /// which reports relative mouse movement even when the cursor can't move,
+    /// e.g. because it is grabbed with [`winit::window::Window::set_cursor_grab`]
+    /// in [`winit::window::CursorGrabMode::Locked`] mode.
+    /// Read it with [`egui::PointerState::motion`] instead of the pointer position.
+    ///
+    /// Device events arrive even when the window is not focused, so they are ignored then.
+    pub fn on_device_event(&mut self, event: &winit::event::DeviceEvent) -> EventResponse {
+        let focused = self.egui_winit.egui_input().focused;
+        match event {
+            winit::event::DeviceEvent::MouseMotion { delta } if focused => {
+                self.egui_winit.on_mouse_motion(*delta);
+                EventResponse {
+                    consumed: false,
+                    repaint: true,
+                }
+            }
+            _ => EventResponse {
+                consumed: false,
+                repaint: false,
+            },
+        }
+    }
+
+    /// Connect egui to screen readers through [AccessKit](https://accesskit.dev/).
+    ///
+    /// This can also be done when building, with [`EguiGliumBuilder::with_accesskit`].
+    ///
+    /// Call this before the window is first shown,
+    /// i.e. create it with [`winit::window::WindowAttributes::with_visible`] set to `false`
+    /// and make it visible afterwards.
+    ///
+    /// Events from the adapter arrive as user events on your event loop,
+    /// and should be forwarded to [`Self::on_accesskit_event`].
+    #[cfg(feature = "accesskit")]
+    pub fn init_accesskit<T: From<egui_winit::accesskit_winit::Event> + Send>(
+        &mut self,
+        event_loop: &winit::event_loop::ActiveEventLoop,
+        window: &winit::window::Window,
+        event_loop_proxy: winit::event_loop::EventLoopProxy<T>,
+    ) {
+        self.egui_winit
+            .init_accesskit(event_loop, window, event_loop_proxy);
+    }
+
+    /// Forward an event from the AccessKit adapter set up by [`Self::init_accesskit`].
+    ///
+    /// Action requests from the screen reader are routed to egui as input.
+    #[cfg(feature = "accesskit")]
+    pub fn on_accesskit_event(
+        &mut self,
+        event: &egui_winit::accesskit_winit::WindowEvent,
+    ) -> EventResponse {
+        on_accesskit_event(&mut self.egui_winit, event)
+    }
+
+    /// Save [`egui::Memory`] and the window geometry now, instead of waiting for the next autosave.
+    ///
+    /// This also happens when `self` is dropped.
+    #[cfg(feature = "persistence")]
+    pub fn save(&mut self) {
+        if let Some(persistence) = &mut self.persistence {
+            persistence.save();
+        }
+    }
+
+    /// Did egui ask to close this viewport during the last call to [`Self::run`]?
+    ///
+    /// This happens on [`egui::ViewportCommand::Close`], or when the window was asked to close
+    /// and the app did not respond with [`egui::ViewportCommand::CancelClose`].
+    ///
+    /// It is up to you to close the window, or exit the event loop.
+    pub fn should_close(&self) -> bool {
+        self.should_close
+    }
+
+    /// How long after the last call to [`Self::run`] egui wants to run again,
+    /// e.g. for animations, tooltips or [`egui::Context::request_repaint_after`].
+    ///
+    /// [`std::time::Duration::ZERO`] means as soon as possible,
+    /// and [`std::time::Duration::MAX`] means only on new input.
+    ///
+    /// See [`Self::schedule_repaint`].
+    pub fn repaint_delay(&self) -> std::time::Duration {
+        self.repaint_delay
+    }
+
+    /// Request a redraw of `window` if egui wants one now,
+    /// or set the [`winit::event_loop::ControlFlow`] to wake up when it does.
+    ///
+    /// Call this from [`winit::application::ApplicationHandler::about_to_wait`].
+    pub fn schedule_repaint(
+        &self,
+        event_loop: &winit::event_loop::ActiveEventLoop,
+        window: &winit::window::Window,
+    ) {
+        use winit::event_loop::ControlFlow;
+
+        match self.repaint_at {
+            Some(repaint_at) if repaint_at <= std::time::Instant::now() => {
+                window.request_redraw();
+                event_loop.set_control_flow(ControlFlow::Wait);
+            }
+            Some(repaint_at) => event_loop.set_control_flow(ControlFlow::WaitUntil(repaint_at)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
+        }
     }
 
     /// Runs the main egui render.
     ///
+    /// The [`egui::ViewportInfo`] egui sees (size, position, monitor size, focus, fullscreen, …)
+    /// is read from `window` first,
+    /// and [`egui::ViewportCommand`]s sent to this viewport are applied to `window` afterwards.
+    ///
     /// Call [`Self::paint`] later to paint.
     pub fn run(&mut self, window: &winit::window::Window, run_ui: impl FnMut(&egui::Context)) {
+        egui_winit::update_viewport_info(
+            &mut self.viewport_info,
+            self.egui_winit.egui_ctx(),
+            window,
+            false,
+        );
+        self.egui_winit
+            .egui_input_mut()
+            .viewports
+            .insert(self.viewport_id, self.viewport_info.clone());
+        // The events should only be delivered once:
+        self.viewport_info.events.clear();
+
         let raw_input = self.egui_winit.take_egui_input(window);
+        let close_requested = raw_input.viewport().close_requested();
+
         let egui::FullOutput {
             platform_output,
             textures_delta,
             shapes,
+            viewport_output,
             ..
         } = self.egui_ctx().run(raw_input, run_ui);
 
         self.egui_winit
             .handle_platform_output(window, platform_output);
 
+        self.should_close = false;
+        self.repaint_delay = std::time::Duration::MAX;
+        if let Some(viewport_output) = viewport_output.get(&self.viewport_id) {
+            self.repaint_delay = viewport_output.repaint_delay;
+            let commands = viewport_output.commands.clone();
+
+            if close_requested {
+                if commands.contains(&egui::ViewportCommand::CancelClose) {
+                    log::debug!("Closing of viewport canceled with ViewportCommand::CancelClose");
+                } else {
+                    self.should_close = true;
+                }
+            }
+
+            self.actions_requested.process_viewport_commands(
+                self.egui_winit.egui_ctx(),
+                &mut self.viewport_info,
+                commands,
+                window,
+            );
+
+            // A `ViewportCommand::Close` only takes effect if it isn't canceled on the next frame:
+            if self.viewport_info.close_requested() {
+                self.repaint_delay = std::time::Duration::ZERO;
+            }
+        }
+
+        self.repaint_at = std::time::Instant::now().checked_add(self.repaint_delay);
+
+        #[cfg(feature = "persistence")]
+        if let Some(persistence) = &mut self.persistence {
+            persistence.update_window_geometry(window);
+            persistence.autosave();
+        }
+
+        self.actions_requested
+            .handle_clipboard(&mut self.egui_winit);
+
         self.shapes = shapes;
         self.textures_delta.append(textures_delta);
     }
 
     /// Paint the results of the last call to [`Self::run`].
+    ///
+    /// If a screenshot was requested with [`egui::ViewportCommand::Screenshot`],
+    /// `target` is read back after painting and delivered as an [`egui::Event::Screenshot`]
+    /// on the next frame.
+    ///
+    /// Panics on failure. See [`Self::try_paint`].
     pub fn paint<T: glium::Surface>(
         &mut self,
-        display: &glium::Display<WindowSurface>,
+        facade: &dyn glium::backend::Facade,
         target: &mut T,
     ) {
+        self.try_paint(facade, target)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    /// Paint the results of the last call to [`Self::run`].
+    ///
+    /// See [`Self::paint`].
+    pub fn try_paint<T: glium::Surface>(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+        target: &mut T,
+    ) -> Result<(), Error> {
         let shapes = std::mem::take(&mut self.shapes);
         let textures_delta = std::mem::take(&mut self.textures_delta);
         let clipped_primitives = self
             .egui_ctx()
             .tessellate(shapes, self.egui_ctx().pixels_per_point());
-        self.painter.paint_and_update_textures(
-            display,
+        self.painter.try_paint_and_update_textures(
+            facade,
             target,
             self.egui_ctx().pixels_per_point(),
             &clipped_primitives,
             &textures_delta,
+        )?;
+
+        if self.actions_requested.handle_screenshots(
+            &mut self.egui_winit,
+            self.viewport_id,
+            &self.painter,
+            facade,
+            target,
+        )? {
+            // Deliver the screenshot right away:
+            self.repaint_delay = std::time::Duration::ZERO;
+            self.repaint_at = Some(std::time::Instant::now());
+        }
+
+        Ok(())
+    }
+
+    /// Paint the results of the last call to [`Self::run`] into a new texture, instead of the window.
+    ///
+    /// The texture has the size of the window egui was run for, in pixels,
+    /// since that is what the UI was laid out for.
+    /// To paint a UI of another size, use [`Painter::paint_to_texture`] with your own layout.
+    ///
+    /// Panics on failure. See [`Self::try_paint_to_texture`].
+    pub fn paint_to_texture(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+    ) -> glium::texture::SrgbTexture2d {
+        self.try_paint_to_texture(facade)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`Self::paint_to_texture`], but returns an error instead of panicking.
+    pub fn try_paint_to_texture(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+    ) -> Result<glium::texture::SrgbTexture2d, Error> {
+        let shapes = std::mem::take(&mut self.shapes);
+        let textures_delta = std::mem::take(&mut self.textures_delta);
+        let pixels_per_point = self.egui_ctx().pixels_per_point();
+        let clipped_primitives = self.egui_ctx().tessellate(shapes, pixels_per_point);
+        let size_in_pixels = (self.egui_ctx().viewport_rect().size() * pixels_per_point).round();
+        self.painter.try_paint_to_texture(
+            facade,
+            [size_in_pixels.x as u32, size_in_pixels.y as u32],
+            pixels_per_point,
+            &clipped_primitives,
+            &textures_delta,
+        )
+    }
+}
+
+/// See [`EguiGlium::on_accesskit_event`].
+#[cfg(feature = "accesskit")]
+fn on_accesskit_event(
+    egui_winit: &mut egui_winit::State,
+    event: &egui_winit::accesskit_winit::WindowEvent,
+) -> EventResponse {
+    use egui_winit::accesskit_winit::WindowEvent;
+
+    match event {
+        WindowEvent::InitialTreeRequested => {
+            egui_winit.egui_ctx().enable_accesskit();
+        }
+        WindowEvent::ActionRequested(request) => {
+            egui_winit.on_accesskit_action_request(request.clone());
+        }
+        WindowEvent::AccessibilityDeactivated => {
+            egui_winit.egui_ctx().disable_accesskit();
+        }
+    }
+
+    EventResponse {
+        consumed: true,
+        repaint: true,
+    }
+}
+
+/// The name of the executable, used as app id if none is given.
+#[cfg(feature = "persistence")]
+fn default_app_id() -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.file_stem()?.to_string_lossy().into_owned())
+}
+
+// ----------------------------------------------------------------------------
+
+/// Sets up AccessKit once the [`egui_winit::State`] exists.
+#[cfg(feature = "accesskit")]
+type InitAccessKit<'a> = Box<dyn FnOnce(&mut egui_winit::State, &winit::window::Window) + 'a>;
+
+/// Configures an [`EguiGlium`]. Create with [`EguiGlium::builder`].
+#[cfg_attr(
+    feature = "accesskit",
+    doc = "",
+    doc = "The lifetime is that of the event loop given to [`Self::with_accesskit`], if any."
+)]
+#[derive(Default)]
+pub struct EguiGliumBuilder<'a> {
+    egui_ctx: egui::Context,
+    viewport_id: egui::ViewportId,
+    theme: Option<winit::window::Theme>,
+    max_texture_side: Option<usize>,
+    painter: Option<Painter>,
+
+    #[cfg(feature = "persistence")]
+    app_id: Option<String>,
+
+    #[cfg(feature = "persistence")]
+    autosave_interval: Option<std::time::Duration>,
+
+    #[cfg(feature = "accesskit")]
+    init_accesskit: Option<InitAccessKit<'a>>,
+
+    _lifetime: std::marker::PhantomData<&'a ()>,
+}
+
+impl<'a> EguiGliumBuilder<'a> {
+    /// Use an existing context, e.g. one shared with other windows.
+    ///
+    /// By default a new one is created.
+    pub fn with_egui_ctx(mut self, egui_ctx: egui::Context) -> Self {
+        self.egui_ctx = egui_ctx;
+        self
+    }
+
+    /// Defaults to [`egui::ViewportId::ROOT`].
+    pub fn with_viewport_id(mut self, viewport_id: egui::ViewportId) -> Self {
+        self.viewport_id = viewport_id;
+        self
+    }
+
+    /// The theme of the window, if known.
+    ///
+    /// Used by egui to pick dark or light mode when following the system theme.
+    pub fn with_theme(mut self, theme: winit::window::Theme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Tell egui to keep its textures smaller than this.
+    ///
+    /// This can only lower the limit of the OpenGL driver, not raise it.
+    pub fn with_max_texture_side(mut self, max_texture_side: usize) -> Self {
+        self.max_texture_side = Some(max_texture_side);
+        self
+    }
+
+    /// Use a [`Painter`] you have created and configured yourself.
+    ///
+    /// It must have been created for the same facade that is passed to [`Self::build`].
+    pub fn with_painter(mut self, painter: Painter) -> Self {
+        self.painter = Some(painter);
+        self
+    }
+
+    /// Where to save [`egui::Memory`] and the window geometry between runs:
+    /// a directory with this name in the app data directory of the platform.
+    ///
+    /// Defaults to the name of the executable.
+    /// The saved state is restored when the [`EguiGlium`] is built.
+    /// Only the root viewport is saved.
+    #[cfg(feature = "persistence")]
+    pub fn with_app_id(mut self, app_id: impl Into<String>) -> Self {
+        self.app_id = Some(app_id.into());
+        self
+    }
+
+    /// How often to save while running. Defaults to every 30 seconds.
+    #[cfg(feature = "persistence")]
+    pub fn with_autosave_interval(mut self, autosave_interval: std::time::Duration) -> Self {
+        self.autosave_interval = Some(autosave_interval);
+        self
+    }
+
+    /// Connect egui to screen readers through [AccessKit](https://accesskit.dev/),
+    /// as [`EguiGlium::init_accesskit`] does.
+    ///
+    /// The window passed to [`Self::build`] must not have been shown yet.
+    #[cfg(feature = "accesskit")]
+    pub fn with_accesskit<T: From<egui_winit::accesskit_winit::Event> + Send + 'a>(
+        mut self,
+        event_loop: &'a winit::event_loop::ActiveEventLoop,
+        event_loop_proxy: winit::event_loop::EventLoopProxy<T>,
+    ) -> Self {
+        self.init_accesskit = Some(Box::new(move |egui_winit, window| {
+            egui_winit.init_accesskit(event_loop, window, event_loop_proxy);
+        }));
+        self
+    }
+
+    pub fn build(
+        self,
+        facade: &dyn glium::backend::Facade,
+        window: &winit::window::Window,
+        event_loop: &dyn HasDisplayHandle,
+    ) -> Result<EguiGlium, Error> {
+        EguiGlium::from_builder(self, facade, window, event_loop)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(all(test, feature = "accesskit"))]
+mod tests {
+    use egui::accesskit;
+    use egui_winit::accesskit_winit::WindowEvent;
+    use raw_window_handle::{DisplayHandle, HandleError, HasDisplayHandle};
+
+    /// Stands in for the event loop, since the tests have no display to connect to.
+    struct NoDisplay;
+
+    impl HasDisplayHandle for NoDisplay {
+        fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+            Err(HandleError::Unavailable)
+        }
+    }
+
+    fn run(egui_winit: &mut egui_winit::State) -> egui::FullOutput {
+        let raw_input = egui_winit.egui_input_mut().take();
+        egui_winit.egui_ctx().run(raw_input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let _ = ui.button("Click me");
+            });
+        })
+    }
+
+    #[test]
+    fn accesskit_events_reach_egui() {
+        let mut egui_winit = egui_winit::State::new(
+            egui::Context::default(),
+            egui::ViewportId::ROOT,
+            &NoDisplay,
+            None,
+            None,
+            None,
         );
+        assert!(run(&mut egui_winit)
+            .platform_output
+            .accesskit_update
+            .is_none());
+
+        let response =
+            super::on_accesskit_event(&mut egui_winit, &WindowEvent::InitialTreeRequested);
+        assert!(response.repaint);
+        let update = run(&mut egui_winit)
+            .platform_output
+            .accesskit_update
+            .expect("a tree update once a screen reader asked for it");
+        let (target, _) = update.nodes.last().expect("nodes in the tree");
+
+        let request = accesskit::ActionRequest {
+            action: accesskit::Action::Click,
+            target: *target,
+            data: None,
+        };
+        let _ = super::on_accesskit_event(
+            &mut egui_winit,
+            &WindowEvent::ActionRequested(request.clone()),
+        );
+        let raw_input = egui_winit.egui_input_mut().take();
+        assert!(raw_input
+            .events
+            .contains(&egui::Event::AccessKitActionRequest(request)));
+
+        let _ = super::on_accesskit_event(&mut egui_winit, &WindowEvent::AccessibilityDeactivated);
+        assert!(run(&mut egui_winit)
+            .platform_output
+            .accesskit_update
+            .is_none());
     }
 }
@@ -0,0 +1,206 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use glium::{
+    texture::{SrgbTexture2d, Texture2d},
+    uniforms::{SamplerBehavior, UniformValue},
+};
+
+/// A texture you created yourself, to show in egui with [`crate::Painter::register_native_texture`].
+///
+/// Egui works in gamma space with premultiplied alpha,
+/// and the variant tells the painter how to get there from what is in the texture.
+#[derive(Clone)]
+pub enum NativeTexture {
+    /// sRGB colors with premultiplied alpha, like the textures egui itself uploads.
+    Srgb(Rc<SrgbTexture2d>),
+
+    /// Linear colors with premultiplied alpha, e.g. a render target of a 3D scene.
+    ///
+    /// The texture is sampled as-is, without any sRGB decoding, and converted to gamma in the shader.
+    Linear(Rc<Texture2d>),
+
+    /// A single channel texture, e.g. a depth or height map, shown as opaque gray.
+    ///
+    /// The red channel is used, as a linear value.
+    Gray(Rc<Texture2d>),
+
+    /// A single channel texture, e.g. a mask, shown as white with the red channel as alpha.
+    ///
+    /// Tint it with the color of the [`egui::Image`].
+    Alpha(Rc<Texture2d>),
+
+    /// High dynamic range linear colors with premultiplied alpha, e.g. an `F16F16F16F16` render target.
+    ///
+    /// Alpha is expected in `0-1`, while the colors may go above it.
+    ///
+    /// Exposure and tone mapping are applied before display,
+    /// as set with [`crate::Painter::set_hdr_options`].
+    Hdr(Rc<Texture2d>),
+}
+
+impl NativeTexture {
+    pub(crate) fn mipmap_levels(&self) -> u32 {
+        match self {
+            Self::Srgb(texture) => texture.get_mipmap_levels(),
+            Self::Linear(texture)
+            | Self::Gray(texture)
+            | Self::Alpha(texture)
+            | Self::Hdr(texture) => texture.get_mipmap_levels(),
+        }
+    }
+
+    pub(crate) fn uniform_value(&self, sampler: SamplerBehavior) -> UniformValue<'_> {
+        match self {
+            Self::Srgb(texture) => UniformValue::SrgbTexture2d(texture, Some(sampler)),
+            Self::Linear(texture)
+            | Self::Gray(texture)
+            | Self::Alpha(texture)
+            | Self::Hdr(texture) => UniformValue::Texture2d(texture, Some(sampler)),
+        }
+    }
+
+    /// The value of `u_texture_kind` in the fragment shaders.
+    pub(crate) fn shader_kind(&self) -> i32 {
+        match self {
+            Self::Srgb(_) => 0,
+            Self::Linear(_) => 1,
+            Self::Gray(_) => 2,
+            Self::Alpha(_) => 3,
+            Self::Hdr(_) => 4,
+        }
+    }
+}
+
+/// How to display a [`NativeTexture::Hdr`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HdrOptions {
+    /// In stops: the colors are multiplied by `2^exposure` before tone mapping.
+    pub exposure: f32,
+
+    pub tone_mapping: ToneMapping,
+}
+
+impl Default for HdrOptions {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            tone_mapping: ToneMapping::Aces,
+        }
+    }
+}
+
+/// How to map high dynamic range colors into the `0-1` range of the screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMapping {
+    /// No tone mapping: everything above `1` is clipped.
+    Clamp,
+
+    /// `c / (1 + c)`. Never clips, but desaturates bright colors.
+    Reinhard,
+
+    /// An approximation of the ACES filmic curve, with more contrast than [`Self::Reinhard`].
+    #[default]
+    Aces,
+}
+
+impl ToneMapping {
+    /// The value of `u_tone_mapping` in the fragment shaders.
+    pub(crate) fn shader_value(self) -> i32 {
+        match self {
+            Self::Clamp => 0,
+            Self::Reinhard => 1,
+            Self::Aces => 2,
+        }
+    }
+}
+
+impl From<Rc<SrgbTexture2d>> for NativeTexture {
+    fn from(texture: Rc<SrgbTexture2d>) -> Self {
+        Self::Srgb(texture)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Ids of native textures to free, shared between a [`crate::Painter`] and its handles.
+#[derive(Default)]
+pub(crate) struct PendingFree(Rc<RefCell<Vec<egui::TextureId>>>);
+
+impl PendingFree {
+    pub fn take(&self) -> Vec<egui::TextureId> {
+        std::mem::take(&mut self.0.borrow_mut())
+    }
+}
+
+/// An owned native texture, created with [`crate::Painter::register_native_texture_handle`].
+///
+/// When dropped, the texture is freed from the [`crate::Painter`] before its next paint.
+#[derive(Debug)]
+#[must_use = "The texture is freed when the handle is dropped"]
+pub struct NativeTextureHandle {
+    id: egui::TextureId,
+
+    /// Weak, so handles outliving the painter do nothing.
+    pending_free: Weak<RefCell<Vec<egui::TextureId>>>,
+}
+
+impl NativeTextureHandle {
+    pub(crate) fn new(id: egui::TextureId, pending_free: &PendingFree) -> Self {
+        Self {
+            id,
+            pending_free: Rc::downgrade(&pending_free.0),
+        }
+    }
+
+    /// The id to show the texture with, e.g. in an [`egui::Image`].
+    pub fn id(&self) -> egui::TextureId {
+        self.id
+    }
+}
+
+impl Drop for NativeTextureHandle {
+    fn drop(&mut self) {
+        if let Some(pending_free) = self.pending_free.upgrade() {
+            pending_free.borrow_mut().push(self.id);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{NativeTextureHandle, PendingFree};
+
+    #[test]
+    fn dropped_handle_is_pending_free() {
+        let pending_free = PendingFree::default();
+        let kept = NativeTextureHandle::new(egui::TextureId::User(0), &pending_free);
+        let dropped = NativeTextureHandle::new(egui::TextureId::User(1), &pending_free);
+
+        assert!(pending_free.take().is_empty());
+
+        drop(dropped);
+        assert_eq!(pending_free.take(), vec![egui::TextureId::User(1)]);
+        assert!(
+            pending_free.take().is_empty(),
+            "take should empty the queue"
+        );
+
+        drop(kept);
+        assert_eq!(pending_free.take(), vec![egui::TextureId::User(0)]);
+    }
+
+    #[test]
+    fn handle_outliving_painter_does_nothing() {
+        let pending_free = PendingFree::default();
+        let handle = NativeTextureHandle::new(egui::TextureId::User(0), &pending_free);
+
+        drop(pending_free);
+        assert!(handle.pending_free.upgrade().is_none());
+        drop(handle);
+    }
+}
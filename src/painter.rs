@@ -2,10 +2,17 @@
 #![allow(semicolon_in_expressions_from_macros)] // glium::program! macro
 
 use egui::{
-    epaint::{textures::TextureFilter, Primitive},
+    epaint::{
+        textures::{TextureFilter, TextureWrapMode},
+        Primitive,
+    },
     TextureOptions,
 };
-use glium::glutin::surface::WindowSurface;
+
+use crate::{
+    native_texture::{NativeTextureHandle, PendingFree},
+    Error, HdrOptions, NativeTexture,
+};
 
 use {
     egui::{emath::Rect, epaint::Mesh},
@@ -13,27 +20,88 @@ use {
         implement_vertex,
         index::PrimitiveType,
         texture::{self, srgb_texture2d::SrgbTexture2d},
-        uniform,
-        uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction},
+        uniforms::{
+            MagnifySamplerFilter, MinifySamplerFilter, SamplerBehavior, SamplerWrapFunction,
+        },
     },
-    std::rc::Rc,
+    std::{collections::VecDeque, rc::Rc, sync::Arc, time::Duration},
 };
 
+/// Paints egui using [`glium`].
+///
+/// Works with any [`glium::backend::Facade`]: a window's [`glium::Display`],
+/// or an `Rc<glium::backend::Context>` wrapping a headless or surfaceless context.
 pub struct Painter {
     max_texture_side: usize,
     program: glium::Program,
 
     textures: ahash::HashMap<egui::TextureId, EguiTexture>,
 
+    /// `Some` if we keep CPU copies of the egui-managed textures. See [`Painter::set_retain_textures`].
+    retained_textures: Option<RetainedTextures>,
+
     /// [`egui::TextureId::User`] index
     next_native_tex_id: u64,
+
+    /// Native textures whose [`NativeTextureHandle`] was dropped, to free before the next paint.
+    pending_free: PendingFree,
+
+    /// Reused across frames, and only reallocated when a frame needs more room.
+    vertex_buffer: Option<glium::VertexBuffer<Vertex>>,
+    index_buffer: Option<glium::IndexBuffer<u32>>,
+
+    /// All meshes of a frame, concatenated so they can be uploaded at once.
+    /// Kept around to avoid reallocating every frame.
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+
+    /// Collected since the last call to [`Self::take_stats`].
+    stats: PainterStats,
+
+    /// `Some` if GPU timing is enabled.
+    gpu_timer: Option<GpuTimer>,
+}
+
+/// What a [`Painter`] did, as returned by [`Painter::take_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PainterStats {
+    /// Number of meshes drawn. Paint callbacks are not counted.
+    pub draw_calls: usize,
+
+    /// Number of vertices uploaded.
+    pub vertices: usize,
+
+    /// Number of indices uploaded.
+    pub indices: usize,
+
+    /// Number of bytes of texture data uploaded, both new textures and partial updates.
+    pub texture_upload_bytes: usize,
+
+    /// Number of textures the painter holds right now, including native textures.
+    pub textures_alive: usize,
+
+    /// How long the GPU spent drawing egui in a recent frame, if GPU timing is enabled.
+    ///
+    /// The result of a frame only becomes available a few frames later,
+    /// so this lags behind the other numbers.
+    /// See [`Painter::set_gpu_timing`].
+    pub gpu_time: Option<Duration>,
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    a_pos: [f32; 2],
+    a_tc: [f32; 2],
+    a_srgba: [u8; 4],
+}
+implement_vertex!(Vertex, a_pos, a_tc, a_srgba);
+
 fn create_program(
     facade: &dyn glium::backend::Facade,
     vertex_shader: &str,
     fragment_shader: &str,
-) -> glium::program::Program {
+) -> Result<glium::program::Program, glium::ProgramCreationError> {
     let input = glium::program::ProgramCreationInput::SourceCode {
         vertex_shader,
         tessellation_control_shader: None,
@@ -46,102 +114,324 @@ fn create_program(
     };
 
     glium::program::Program::new(facade, input)
-        .unwrap_or_else(|err| panic!("Failed to compile shader: {}", err))
+}
+
+/// Compile the egui shaders for the newest GLSL version the facade supports.
+fn create_egui_program(facade: &dyn glium::backend::Facade) -> Result<glium::Program, Error> {
+    use glium::CapabilitiesSource as _;
+
+    let program = if facade
+        .get_context()
+        .is_glsl_version_supported(&glium::Version(glium::Api::Gl, 1, 4))
+    {
+        log::info!("Using GL 1.4");
+        create_program(
+            facade,
+            include_str!("shader/vertex_140.glsl"),
+            include_str!("shader/fragment_140.glsl"),
+        )?
+    } else if facade
+        .get_context()
+        .is_glsl_version_supported(&glium::Version(glium::Api::Gl, 1, 2))
+    {
+        log::info!("Using GL 1.2");
+        create_program(
+            facade,
+            include_str!("shader/vertex_120.glsl"),
+            include_str!("shader/fragment_120.glsl"),
+        )?
+    } else if facade
+        .get_context()
+        .is_glsl_version_supported(&glium::Version(glium::Api::GlEs, 3, 0))
+    {
+        log::info!("Using GL ES 3.0");
+        create_program(
+            facade,
+            include_str!("shader/vertex_300es.glsl"),
+            include_str!("shader/fragment_300es.glsl"),
+        )?
+    } else if facade
+        .get_context()
+        .is_glsl_version_supported(&glium::Version(glium::Api::GlEs, 1, 0))
+    {
+        log::info!("Using GL ES 1.0");
+        create_program(
+            facade,
+            include_str!("shader/vertex_100es.glsl"),
+            include_str!("shader/fragment_100es.glsl"),
+        )?
+    } else {
+        return Err(Error::UnsupportedGlslVersion(*facade.get_version()));
+    };
+
+    Ok(program)
 }
 
 impl Painter {
+    /// Panics if no shader compiles on this OpenGL version. See [`Self::try_new`].
     pub fn new(facade: &dyn glium::backend::Facade) -> Painter {
+        Self::try_new(facade).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`Self::new`], but returns an error if no shader compiles.
+    pub fn try_new(facade: &dyn glium::backend::Facade) -> Result<Painter, Error> {
         use glium::CapabilitiesSource as _;
         let max_texture_side = facade.get_capabilities().max_texture_size as _;
 
-        let program = if facade
-            .get_context()
-            .is_glsl_version_supported(&glium::Version(glium::Api::Gl, 1, 4))
-        {
-            log::info!("Using GL 1.4");
-            create_program(
-                facade,
-                include_str!("shader/vertex_140.glsl"),
-                include_str!("shader/fragment_140.glsl"),
-            )
-        } else if facade
-            .get_context()
-            .is_glsl_version_supported(&glium::Version(glium::Api::Gl, 1, 2))
-        {
-            log::info!("Using GL 1.2");
-            create_program(
-                facade,
-                include_str!("shader/vertex_120.glsl"),
-                include_str!("shader/fragment_120.glsl"),
-            )
-        } else if facade
-            .get_context()
-            .is_glsl_version_supported(&glium::Version(glium::Api::GlEs, 3, 0))
-        {
-            log::info!("Using GL ES 3.0");
-            create_program(
-                facade,
-                include_str!("shader/vertex_300es.glsl"),
-                include_str!("shader/fragment_300es.glsl"),
-            )
-        } else if facade
-            .get_context()
-            .is_glsl_version_supported(&glium::Version(glium::Api::GlEs, 1, 0))
-        {
-            log::info!("Using GL ES 1.0");
-            create_program(
-                facade,
-                include_str!("shader/vertex_100es.glsl"),
-                include_str!("shader/fragment_100es.glsl"),
-            )
-        } else {
-            panic!(
-                "Failed to find a compatible shader for OpenGL version {:?}",
-                facade.get_version()
-            )
-        };
+        let program = create_egui_program(facade)?;
 
-        Painter {
+        Ok(Painter {
             max_texture_side,
             program,
             textures: Default::default(),
+            retained_textures: None,
             next_native_tex_id: 0,
-        }
+            pending_free: Default::default(),
+            vertex_buffer: None,
+            index_buffer: None,
+            vertices: Default::default(),
+            indices: Default::default(),
+            stats: Default::default(),
+            gpu_timer: None,
+        })
     }
 
     pub fn max_texture_side(&self) -> usize {
         self.max_texture_side
     }
 
+    /// Keep a CPU copy of every texture egui sends us, so [`Self::recreate`] can upload them again.
+    ///
+    /// Off by default, since it doubles the memory used for textures.
+    /// Turn it on before the first frame, or textures egui already sent will be missing.
+    pub fn set_retain_textures(&mut self, retain: bool) {
+        if retain {
+            self.retained_textures.get_or_insert_with(Default::default);
+        } else {
+            self.retained_textures = None;
+        }
+    }
+
+    /// Panics on failure. See [`Self::try_recreate`].
+    pub fn recreate(&mut self, facade: &dyn glium::backend::Facade) {
+        self.try_recreate(facade)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    /// Move the painter to a new OpenGL context, e.g. after the old one was lost.
+    ///
+    /// The shader program and buffers are created again,
+    /// and if [`Self::set_retain_textures`] is on, all egui textures are uploaded again.
+    ///
+    /// Native textures belong to the old context, so they are dropped.
+    /// Create them again and re-register them under their old ids
+    /// with [`Self::replace_native_texture`].
+    pub fn try_recreate(&mut self, facade: &dyn glium::backend::Facade) -> Result<(), Error> {
+        use glium::CapabilitiesSource as _;
+
+        self.max_texture_side = facade.get_capabilities().max_texture_size as _;
+        self.program = create_egui_program(facade)?;
+        self.vertex_buffer = None;
+        self.index_buffer = None;
+        if self.gpu_timer.is_some() {
+            self.gpu_timer = Some(Default::default());
+        }
+
+        self.textures.clear();
+        if let Some(retained_textures) = self.retained_textures.take() {
+            let result = retained_textures
+                .iter()
+                .try_for_each(|(id, image_delta)| self.try_set_texture(facade, id, image_delta));
+            self.retained_textures = Some(retained_textures);
+            result?;
+        }
+
+        Ok(())
+    }
+
+    /// What the painter did since the last call to this.
+    ///
+    /// Call it once per frame, after painting, to get per-frame numbers.
+    pub fn take_stats(&mut self) -> PainterStats {
+        let mut stats = std::mem::take(&mut self.stats);
+        stats.textures_alive = self.textures.len();
+        stats.gpu_time = self.gpu_timer.as_ref().and_then(|timer| timer.last_result);
+        stats
+    }
+
+    /// Measure how long the GPU spends drawing egui, using OpenGL timer queries.
+    ///
+    /// The painter never waits for the GPU to finish; instead the result is picked up
+    /// a few frames later, and reported in [`PainterStats::gpu_time`].
+    ///
+    /// Off by default. If timer queries are not supported, this stays off.
+    pub fn set_gpu_timing(&mut self, enabled: bool) {
+        if enabled {
+            self.gpu_timer.get_or_insert_with(Default::default);
+        } else {
+            self.gpu_timer = None;
+        }
+    }
+
+    /// Panics on failure. See [`Self::try_paint_and_update_textures`].
     pub fn paint_and_update_textures<T: glium::Surface>(
         &mut self,
-        display: &glium::Display<WindowSurface>,
+        facade: &dyn glium::backend::Facade,
         target: &mut T,
         pixels_per_point: f32,
         clipped_primitives: &[egui::ClippedPrimitive],
         textures_delta: &egui::TexturesDelta,
     ) {
+        self.try_paint_and_update_textures(
+            facade,
+            target,
+            pixels_per_point,
+            clipped_primitives,
+            textures_delta,
+        )
+        .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    /// Like [`Self::paint_and_update_textures`], but returns an error instead of panicking.
+    pub fn try_paint_and_update_textures<T: glium::Surface>(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+        target: &mut T,
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+        textures_delta: &egui::TexturesDelta,
+    ) -> Result<(), Error> {
         for (id, image_delta) in &textures_delta.set {
-            self.set_texture(display, *id, image_delta);
+            self.try_set_texture(facade, *id, image_delta)?;
         }
 
-        self.paint_primitives(display, target, pixels_per_point, clipped_primitives);
+        let result =
+            self.try_paint_primitives(facade, target, pixels_per_point, clipped_primitives);
 
+        // Free textures even if painting failed, so we don't leak them:
         for &id in &textures_delta.free {
             self.free_texture(id);
         }
+
+        result
+    }
+
+    /// Paint a frame into a new texture of the given size, instead of into the window.
+    ///
+    /// The texture is cleared to transparent before painting.
+    /// Like the window, it ends up with gamma-space colors and premultiplied alpha,
+    /// so it can be shown in egui again with [`Self::register_native_texture`].
+    ///
+    /// Panics on failure. See [`Self::try_paint_to_texture`].
+    pub fn paint_to_texture(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+        size_in_pixels: [u32; 2],
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+        textures_delta: &egui::TexturesDelta,
+    ) -> SrgbTexture2d {
+        self.try_paint_to_texture(
+            facade,
+            size_in_pixels,
+            pixels_per_point,
+            clipped_primitives,
+            textures_delta,
+        )
+        .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`Self::paint_to_texture`], but returns an error instead of panicking.
+    pub fn try_paint_to_texture(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+        size_in_pixels: [u32; 2],
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+        textures_delta: &egui::TexturesDelta,
+    ) -> Result<SrgbTexture2d, Error> {
+        use glium::Surface as _;
+
+        let [width, height] = size_in_pixels;
+        let texture = SrgbTexture2d::empty_with_format(
+            facade,
+            texture::SrgbFormat::U8U8U8U8,
+            texture::MipmapsOption::NoMipmap,
+            width,
+            height,
+        )?;
+
+        {
+            let mut target = glium::framebuffer::SimpleFrameBuffer::new(facade, &texture)?;
+            target.clear_color(0.0, 0.0, 0.0, 0.0);
+            self.try_paint_and_update_textures(
+                facade,
+                &mut target,
+                pixels_per_point,
+                clipped_primitives,
+                textures_delta,
+            )?;
+        }
+
+        Ok(texture)
     }
 
     /// Main entry-point for painting a frame.
     /// You should call `target.clear_color(..)` before
     /// and `target.finish()` after this.
+    ///
+    /// `target` can be any [`glium::Surface`], e.g. the window or a texture.
+    /// Its size in pixels is used as the size of the egui screen.
+    ///
+    /// Panics on failure. See [`Self::try_paint_primitives`].
     pub fn paint_primitives<T: glium::Surface>(
         &mut self,
-        display: &glium::Display<WindowSurface>,
+        facade: &dyn glium::backend::Facade,
         target: &mut T,
         pixels_per_point: f32,
         clipped_primitives: &[egui::ClippedPrimitive],
     ) {
+        self.try_paint_primitives(facade, target, pixels_per_point, clipped_primitives)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    /// Like [`Self::paint_primitives`], but returns an error instead of panicking.
+    pub fn try_paint_primitives<T: glium::Surface>(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+        target: &mut T,
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+    ) -> Result<(), Error> {
+        self.free_pending_textures();
+        self.upload_meshes(facade, clipped_primitives)?;
+
+        if let Some(gpu_timer) = &mut self.gpu_timer {
+            gpu_timer.begin_frame();
+        }
+
+        let result =
+            self.paint_uploaded_primitives(facade, target, pixels_per_point, clipped_primitives);
+
+        if let Some(gpu_timer) = &mut self.gpu_timer {
+            gpu_timer.end_frame();
+        }
+
+        result
+    }
+
+    fn paint_uploaded_primitives<T: glium::Surface>(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+        target: &mut T,
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+    ) -> Result<(), Error> {
+        let (width_in_pixels, height_in_pixels) = target.get_dimensions();
+
+        // Each mesh is drawn from its own range of the shared index buffer:
+        let mut index_offset = 0;
+
         for egui::ClippedPrimitive {
             clip_rect,
             primitive,
@@ -149,47 +439,154 @@ impl Painter {
         {
             match primitive {
                 Primitive::Mesh(mesh) => {
-                    self.paint_mesh(target, display, pixels_per_point, clip_rect, mesh);
+                    if let Some(gpu_timer) = &mut self.gpu_timer {
+                        if !gpu_timer.begin_segment(facade) {
+                            self.gpu_timer = None;
+                        }
+                    }
+                    self.paint_mesh(
+                        target,
+                        [width_in_pixels, height_in_pixels],
+                        pixels_per_point,
+                        clip_rect,
+                        mesh,
+                        index_offset,
+                    )?;
+                    index_offset += mesh.indices.len();
                 }
-                Primitive::Callback(_) => {
-                    panic!("Custom rendering callbacks are not implemented in egui_glium");
+                Primitive::Callback(callback) => {
+                    // Draws of the callback would end our timer query, so we start a new one after it:
+                    if let Some(gpu_timer) = &mut self.gpu_timer {
+                        gpu_timer.end_segment();
+                    }
+
+                    if callback.rect.is_positive() {
+                        let info = egui::PaintCallbackInfo {
+                            viewport: callback.rect,
+                            clip_rect: *clip_rect,
+                            pixels_per_point,
+                            screen_size_px: [width_in_pixels, height_in_pixels],
+                        };
+
+                        if let Some(callback) = callback.callback.downcast_ref::<CallbackFn>() {
+                            callback.paint(info, facade, target);
+                        } else {
+                            log::warn!("Warning: Unsupported render callback. Expected egui_glium::CallbackFn");
+                        }
+                    }
                 }
             }
         }
+
+        Ok(())
+    }
+
+    /// Concatenate all meshes into one vertex and index upload.
+    ///
+    /// The indices are rebased so that every mesh can be drawn from the same vertex buffer.
+    fn upload_meshes(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+        clipped_primitives: &[egui::ClippedPrimitive],
+    ) -> Result<(), Error> {
+        self.vertices.clear();
+        self.indices.clear();
+
+        for clipped_primitive in clipped_primitives {
+            if let Primitive::Mesh(mesh) = &clipped_primitive.primitive {
+                let base = self.vertices.len() as u32;
+                self.vertices
+                    .extend_from_slice(bytemuck::cast_slice(&mesh.vertices));
+                self.indices
+                    .extend(mesh.indices.iter().map(|index| base + index));
+            }
+        }
+
+        self.stats.vertices += self.vertices.len();
+        self.stats.indices += self.indices.len();
+
+        if self.indices.is_empty() {
+            return Ok(());
+        }
+
+        self.reserve_buffers(facade, self.vertices.len(), self.indices.len())?;
+
+        if let (Some(vertex_buffer), Some(index_buffer)) = (&self.vertex_buffer, &self.index_buffer)
+        {
+            vertex_buffer
+                .slice(0..self.vertices.len())
+                .expect("vertex buffer should have been reserved")
+                .write(&self.vertices);
+            index_buffer
+                .slice(0..self.indices.len())
+                .expect("index buffer should have been reserved")
+                .write(&self.indices);
+        }
+
+        Ok(())
     }
 
+    /// Grow the shared vertex and index buffers so they can hold at least this many elements.
+    fn reserve_buffers(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+        vertex_count: usize,
+        index_count: usize,
+    ) -> Result<(), Error> {
+        const MIN_CAPACITY: usize = 1024;
+
+        // Grow geometrically so we don't reallocate every time a frame gets slightly bigger.
+        if self
+            .vertex_buffer
+            .as_ref()
+            .map_or(true, |buffer| buffer.len() < vertex_count)
+        {
+            let capacity = vertex_count.next_power_of_two().max(MIN_CAPACITY);
+            self.vertex_buffer = Some(glium::VertexBuffer::empty_dynamic(facade, capacity)?);
+        }
+
+        if self
+            .index_buffer
+            .as_ref()
+            .map_or(true, |buffer| buffer.len() < index_count)
+        {
+            let capacity = index_count.next_power_of_two().max(MIN_CAPACITY);
+            self.index_buffer = Some(glium::IndexBuffer::empty_dynamic(
+                facade,
+                PrimitiveType::TrianglesList,
+                capacity,
+            )?);
+        }
+
+        Ok(())
+    }
+
+    /// Draw a mesh previously uploaded by [`Self::upload_meshes`].
     #[inline(never)] // Easier profiling
     fn paint_mesh<T: glium::Surface>(
         &mut self,
         target: &mut T,
-        display: &glium::Display<WindowSurface>,
+        [width_in_pixels, height_in_pixels]: [u32; 2],
         pixels_per_point: f32,
         clip_rect: &Rect,
         mesh: &Mesh,
-    ) {
+        index_offset: usize,
+    ) -> Result<(), Error> {
         debug_assert!(mesh.is_valid());
 
-        let vertex_buffer = {
-            #[repr(C)]
-            #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-            struct Vertex {
-                a_pos: [f32; 2],
-                a_tc: [f32; 2],
-                a_srgba: [u8; 4],
-            }
-            implement_vertex!(Vertex, a_pos, a_tc, a_srgba);
-
-            let vertices: &[Vertex] = bytemuck::cast_slice(&mesh.vertices);
+        if mesh.indices.is_empty() {
+            return Ok(());
+        }
 
-            // TODO(emilk): we should probably reuse the [`VertexBuffer`] instead of allocating a new one each frame.
-            glium::VertexBuffer::new(display, vertices).unwrap()
+        let (Some(vertex_buffer), Some(index_buffer)) = (&self.vertex_buffer, &self.index_buffer)
+        else {
+            return Ok(());
         };
 
-        // TODO(emilk): we should probably reuse the [`IndexBuffer`] instead of allocating a new one each frame.
-        let index_buffer =
-            glium::IndexBuffer::new(display, PrimitiveType::TrianglesList, &mesh.indices).unwrap();
+        let index_buffer = index_buffer
+            .slice(index_offset..index_offset + mesh.indices.len())
+            .expect("index buffer should have been reserved");
 
-        let (width_in_pixels, height_in_pixels) = display.get_framebuffer_dimensions();
         let width_in_points = width_in_pixels as f32 / pixels_per_point;
         let height_in_points = height_in_pixels as f32 / pixels_per_point;
 
@@ -199,21 +596,47 @@ impl Painter {
                 TextureFilter::Nearest => MagnifySamplerFilter::Nearest,
                 TextureFilter::Linear => MagnifySamplerFilter::Linear,
             };
-            let min_filter = match texture.options.minification {
-                TextureFilter::Nearest => MinifySamplerFilter::Nearest,
-                TextureFilter::Linear => MinifySamplerFilter::Linear,
+            // Only use mipmaps if the texture actually has them, or it would be incomplete:
+            let mipmap_mode = texture
+                .options
+                .mipmap_mode
+                .filter(|_| texture.native.mipmap_levels() > 1);
+            let min_filter = match (texture.options.minification, mipmap_mode) {
+                (TextureFilter::Nearest, None) => MinifySamplerFilter::Nearest,
+                (TextureFilter::Linear, None) => MinifySamplerFilter::Linear,
+                (TextureFilter::Nearest, Some(TextureFilter::Nearest)) => {
+                    MinifySamplerFilter::NearestMipmapNearest
+                }
+                (TextureFilter::Nearest, Some(TextureFilter::Linear)) => {
+                    MinifySamplerFilter::NearestMipmapLinear
+                }
+                (TextureFilter::Linear, Some(TextureFilter::Nearest)) => {
+                    MinifySamplerFilter::LinearMipmapNearest
+                }
+                (TextureFilter::Linear, Some(TextureFilter::Linear)) => {
+                    MinifySamplerFilter::LinearMipmapLinear
+                }
+            };
+
+            let wrap_function = match texture.options.wrap_mode {
+                TextureWrapMode::ClampToEdge => SamplerWrapFunction::Clamp,
+                TextureWrapMode::Repeat => SamplerWrapFunction::Repeat,
+                TextureWrapMode::MirroredRepeat => SamplerWrapFunction::Mirror,
             };
 
-            let sampler = texture
-                .glium_texture
-                .sampled()
-                .magnify_filter(mag_filter)
-                .minify_filter(min_filter)
-                .wrap_function(SamplerWrapFunction::Clamp);
+            let sampler = SamplerBehavior {
+                wrap_function: (wrap_function, wrap_function, wrap_function),
+                minify_filter: min_filter,
+                magnify_filter: mag_filter,
+                ..Default::default()
+            };
 
-            let uniforms = uniform! {
-                u_screen_size: [width_in_points, height_in_points],
-                u_sampler: sampler,
+            let uniforms = MeshUniforms {
+                screen_size: [width_in_points, height_in_points],
+                sampler: texture.native.uniform_value(sampler),
+                texture_kind: texture.native.shader_kind(),
+                exposure: texture.hdr_options.exposure.exp2(),
+                tone_mapping: texture.hdr_options.tone_mapping.shader_value(),
             };
 
             // egui outputs colors with premultiplied alpha:
@@ -264,29 +687,96 @@ impl Painter {
                     width: clip_max_x - clip_min_x,
                     height: clip_max_y - clip_min_y,
                 }),
+                time_elapsed_query: self
+                    .gpu_timer
+                    .as_ref()
+                    .and_then(|gpu_timer| gpu_timer.current_query()),
                 ..Default::default()
             };
 
-            target
-                .draw(
-                    &vertex_buffer,
-                    &index_buffer,
-                    &self.program,
-                    &uniforms,
-                    &params,
-                )
-                .unwrap();
+            target.draw(
+                vertex_buffer,
+                index_buffer,
+                &self.program,
+                &uniforms,
+                &params,
+            )?;
+            self.stats.draw_calls += 1;
+            if let Some(gpu_timer) = &mut self.gpu_timer {
+                gpu_timer.segment_used = true;
+            }
         }
+
+        Ok(())
+    }
+
+    /// Read back the contents of `surface`, e.g. right after painting to it.
+    ///
+    /// Like egui, this assumes the surface contains gamma-space colors with premultiplied alpha.
+    pub fn read_screen_rgba<T: glium::Surface>(
+        &self,
+        facade: &dyn glium::backend::Facade,
+        surface: &T,
+    ) -> Result<egui::ColorImage, Error> {
+        let (width, height) = surface.get_dimensions();
+
+        // Not every surface can be read from, but they can all be blitted to a texture:
+        let texture = glium::texture::Texture2d::empty_with_format(
+            facade,
+            texture::UncompressedFloatFormat::U8U8U8U8,
+            texture::MipmapsOption::NoMipmap,
+            width,
+            height,
+        )?;
+        surface.blit_whole_color_to(
+            &texture.as_surface(),
+            &glium::BlitTarget {
+                left: 0,
+                bottom: 0,
+                width: width as _,
+                height: height as _,
+            },
+            MagnifySamplerFilter::Nearest,
+        );
+
+        let image: glium::texture::RawImage2d<'_, u8> = texture.read();
+
+        // OpenGL stores the rows bottom-to-top:
+        let pixels = image
+            .data
+            .chunks_exact(4 * width as usize)
+            .rev()
+            .flat_map(|row| row.chunks_exact(4))
+            .map(|p| egui::Color32::from_rgba_premultiplied(p[0], p[1], p[2], p[3]))
+            .collect();
+
+        Ok(egui::ColorImage::new([width as _, height as _], pixels))
     }
 
     // ------------------------------------------------------------------------
 
+    /// Panics on failure. See [`Self::try_set_texture`].
     pub fn set_texture(
         &mut self,
         facade: &dyn glium::backend::Facade,
         tex_id: egui::TextureId,
         delta: &egui::epaint::ImageDelta,
     ) {
+        self.try_set_texture(facade, tex_id, delta)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    /// Like [`Self::set_texture`], but returns an error instead of panicking.
+    pub fn try_set_texture(
+        &mut self,
+        facade: &dyn glium::backend::Facade,
+        tex_id: egui::TextureId,
+        delta: &egui::epaint::ImageDelta,
+    ) -> Result<(), Error> {
+        if let Some(retained_textures) = &mut self.retained_textures {
+            retained_textures.set(tex_id, delta);
+        }
+
         let pixels: Vec<(u8, u8, u8, u8)> = match &delta.image {
             egui::ImageData::Color(image) => {
                 assert_eq!(
@@ -303,76 +793,877 @@ impl Painter {
             height: delta.image.height() as _,
             format: glium::texture::ClientFormat::U8U8U8U8,
         };
+        self.stats.texture_upload_bytes += glium_image.data.len() * 4;
+
         let format = texture::SrgbFormat::U8U8U8U8;
-        let mipmaps = texture::MipmapsOption::NoMipmap;
+        let mipmaps = if delta.options.mipmap_mode.is_some() {
+            texture::MipmapsOption::AutoGeneratedMipmaps
+        } else {
+            texture::MipmapsOption::NoMipmap
+        };
 
         if let Some(pos) = delta.pos {
-            // update a sub-region
+            // update a sub-region.
+            // If the texture was created with mipmaps, glium regenerates them after the write.
             if let Some(user_texture) = self.textures.get_mut(&tex_id) {
+                let NativeTexture::Srgb(glium_texture) = &user_texture.native else {
+                    log::warn!("Partial update of {tex_id:?}, which is not an sRGB texture");
+                    return Ok(());
+                };
                 let rect = glium::Rect {
                     left: pos[0] as _,
                     bottom: pos[1] as _,
                     width: glium_image.width,
                     height: glium_image.height,
                 };
-                user_texture
-                    .glium_texture
-                    .main_level()
-                    .write(rect, glium_image);
+                glium_texture.main_level().write(rect, glium_image);
 
                 user_texture.options = delta.options;
             }
         } else {
-            let gl_texture =
-                SrgbTexture2d::with_format(facade, glium_image, format, mipmaps).unwrap();
+            let gl_texture = SrgbTexture2d::with_format(facade, glium_image, format, mipmaps)?;
 
-            let user_texture = EguiTexture::new(gl_texture.into(), delta.options);
+            let user_texture = EguiTexture::new(Rc::new(gl_texture).into(), delta.options);
             self.textures.insert(tex_id, user_texture);
         }
+
+        Ok(())
     }
 
     pub fn free_texture(&mut self, tex_id: egui::TextureId) {
         self.textures.remove(&tex_id);
+        if let Some(retained_textures) = &mut self.retained_textures {
+            retained_textures.free(tex_id);
+        }
     }
 
     fn texture(&self, texture_id: egui::TextureId) -> Option<&EguiTexture> {
         self.textures.get(&texture_id)
     }
 
+    /// The glium texture behind a [`egui::TextureId`], and how egui samples it.
+    ///
+    /// Works for textures managed by egui, such as the font atlas
+    /// or images loaded with [`egui::Context::load_texture`], as well as native textures.
+    ///
+    /// Painting into the texture changes what egui shows.
+    /// Egui may update or free a managed texture at any time, so don't hold on to it for long.
+    ///
+    /// Returns `None` for native textures that are not [`NativeTexture::Srgb`].
+    /// See [`Self::native_texture`] for those.
+    pub fn glium_texture(
+        &self,
+        texture_id: egui::TextureId,
+    ) -> Option<(Rc<SrgbTexture2d>, TextureOptions)> {
+        let texture = self.texture(texture_id)?;
+        match &texture.native {
+            NativeTexture::Srgb(glium_texture) => Some((glium_texture.clone(), texture.options)),
+            _ => None,
+        }
+    }
+
+    /// Like [`Self::glium_texture`], but for any kind of texture.
+    pub fn native_texture(
+        &self,
+        texture_id: egui::TextureId,
+    ) -> Option<(NativeTexture, TextureOptions)> {
+        let texture = self.texture(texture_id)?;
+        Some((texture.native.clone(), texture.options))
+    }
+
+    /// Show a texture of your own in egui, e.g. with [`egui::Image`].
+    ///
+    /// Pass an `Rc<SrgbTexture2d>`, or a [`NativeTexture`] for other kinds of textures.
     pub fn register_native_texture(
         &mut self,
-        native: Rc<SrgbTexture2d>,
+        native: impl Into<NativeTexture>,
         options: TextureOptions,
     ) -> egui::TextureId {
         let id = egui::TextureId::User(self.next_native_tex_id);
         self.next_native_tex_id += 1;
 
-        let texture = EguiTexture::new(native, options);
+        let texture = EguiTexture::new(native.into(), options);
         self.textures.insert(id, texture);
         id
     }
 
+    /// Like [`Self::register_native_texture`], but the texture is freed
+    /// when the returned handle is dropped.
+    pub fn register_native_texture_handle(
+        &mut self,
+        native: impl Into<NativeTexture>,
+        options: TextureOptions,
+    ) -> NativeTextureHandle {
+        self.free_pending_textures();
+        let id = self.register_native_texture(native, options);
+        NativeTextureHandle::new(id, &self.pending_free)
+    }
+
+    /// Free the textures of dropped [`NativeTextureHandle`]s.
+    fn free_pending_textures(&mut self) {
+        for id in self.pending_free.take() {
+            self.free_texture(id);
+        }
+    }
+
     pub fn replace_native_texture(
         &mut self,
         id: egui::TextureId,
-        replacing: Rc<SrgbTexture2d>,
+        replacing: impl Into<NativeTexture>,
         options: TextureOptions,
     ) {
-        let texture = EguiTexture::new(replacing, options);
+        let mut texture = EguiTexture::new(replacing.into(), options);
+        if let Some(old_texture) = self.textures.get(&id) {
+            texture.hdr_options = old_texture.hdr_options;
+        }
         self.textures.insert(id, texture);
     }
+
+    /// Set the exposure and tone mapping of a [`NativeTexture::Hdr`].
+    ///
+    /// Can be changed every frame. It is kept by [`Self::replace_native_texture`].
+    pub fn set_hdr_options(&mut self, id: egui::TextureId, hdr_options: HdrOptions) {
+        if let Some(texture) = self.textures.get_mut(&id) {
+            texture.hdr_options = hdr_options;
+        }
+    }
+
+    pub fn hdr_options(&self, id: egui::TextureId) -> Option<HdrOptions> {
+        self.texture(id).map(|texture| texture.hdr_options)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+type CallbackFnBox = Box<dyn Fn(egui::PaintCallbackInfo, &mut CallbackContext<'_>) + Sync + Send>;
+
+/// A callback function that can be used to compose an [`egui::PaintCallback`] for custom rendering
+/// with [`glium`].
+///
+/// The callback is passed the [`egui::PaintCallbackInfo`] and a [`CallbackContext`] giving access
+/// to the surface egui is currently painting into.
+///
+/// ```no_run
+/// use glium::Surface as _;
+///
+/// # let rect = egui::Rect::EVERYTHING;
+/// let callback = egui::PaintCallback {
+///     rect,
+///     callback: std::sync::Arc::new(egui_glium::CallbackFn::new(|_info, ctx| {
+///         let clear_rect = ctx.clip_rect;
+///         ctx.surface
+///             .clear(Some(&clear_rect), Some((1.0, 0.0, 0.0, 1.0)), false, None, None);
+///     })),
+/// };
+/// ```
+pub struct CallbackFn {
+    f: CallbackFnBox,
 }
 
+impl CallbackFn {
+    pub fn new<F: Fn(egui::PaintCallbackInfo, &mut CallbackContext<'_>) + Sync + Send + 'static>(
+        callback: F,
+    ) -> Self {
+        let f = Box::new(callback);
+        Self { f }
+    }
+
+    fn paint<T: glium::Surface>(
+        &self,
+        info: egui::PaintCallbackInfo,
+        facade: &dyn glium::backend::Facade,
+        target: &mut T,
+    ) {
+        let viewport = glium_rect_from_viewport(&info.viewport_in_pixels());
+        let clip_rect = glium_rect_from_viewport(&info.clip_rect_in_pixels());
+
+        let mut ctx = CallbackContext {
+            surface: CallbackSurface { inner: target },
+            facade,
+            viewport,
+            clip_rect,
+        };
+        (self.f)(info, &mut ctx);
+    }
+}
+
+fn glium_rect_from_viewport(viewport: &egui::epaint::ViewportInPixels) -> glium::Rect {
+    glium::Rect {
+        left: viewport.left_px.max(0) as _,
+        bottom: viewport.from_bottom_px.max(0) as _,
+        width: viewport.width_px.max(0) as _,
+        height: viewport.height_px.max(0) as _,
+    }
+}
+
+/// Everything a [`CallbackFn`] needs to paint with [`glium`].
+pub struct CallbackContext<'a> {
+    /// The surface egui is painting into.
+    pub surface: CallbackSurface<'a>,
+
+    /// The facade the surface belongs to, for creating buffers, programs, etc.
+    pub facade: &'a dyn glium::backend::Facade,
+
+    /// [`egui::PaintCallback::rect`] in physical pixels.
+    ///
+    /// Use this as [`glium::DrawParameters::viewport`].
+    pub viewport: glium::Rect,
+
+    /// The clip rectangle in physical pixels.
+    ///
+    /// Use this as [`glium::DrawParameters::scissor`].
+    pub clip_rect: glium::Rect,
+}
+
+/// A type-erased [`glium::Surface`], so that [`CallbackFn`] can paint into whatever surface
+/// egui is painting into.
+///
+/// It implements [`glium::Surface`], so it can be passed to your own rendering code.
+pub struct CallbackSurface<'a> {
+    inner: &'a mut dyn DynSurface,
+}
+
+impl glium::Surface for CallbackSurface<'_> {
+    fn clear(
+        &mut self,
+        rect: Option<&glium::Rect>,
+        color: Option<(f32, f32, f32, f32)>,
+        color_srgb: bool,
+        depth: Option<f32>,
+        stencil: Option<i32>,
+    ) {
+        self.inner
+            .dyn_clear(rect, color, color_srgb, depth, stencil);
+    }
+
+    fn get_dimensions(&self) -> (u32, u32) {
+        self.inner.dyn_get_dimensions()
+    }
+
+    fn get_depth_buffer_bits(&self) -> Option<u16> {
+        self.inner.dyn_get_depth_buffer_bits()
+    }
+
+    fn get_stencil_buffer_bits(&self) -> Option<u16> {
+        self.inner.dyn_get_stencil_buffer_bits()
+    }
+
+    fn draw<'a, 'b, V, I, U>(
+        &mut self,
+        vertices: V,
+        indices: I,
+        program: &glium::Program,
+        uniforms: &U,
+        draw_parameters: &glium::DrawParameters<'_>,
+    ) -> Result<(), glium::DrawError>
+    where
+        V: glium::vertex::MultiVerticesSource<'b>,
+        I: Into<glium::index::IndicesSource<'a>>,
+        U: glium::uniforms::Uniforms,
+    {
+        let vertices = DynVertices(vertices.iter().collect());
+
+        let mut values = Vec::new();
+        uniforms.visit_values(|name, value| values.push((name.to_owned(), value)));
+        let uniforms = DynUniforms(values);
+
+        self.inner.dyn_draw(
+            vertices,
+            indices.into(),
+            program,
+            &uniforms,
+            draw_parameters,
+        )
+    }
+
+    fn blit_buffers_from_frame(
+        &self,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    ) {
+        self.inner
+            .dyn_blit_buffers_from_frame(source_rect, target_rect, filter, mask);
+    }
+
+    fn blit_buffers_from_simple_framebuffer(
+        &self,
+        source: &glium::framebuffer::SimpleFrameBuffer<'_>,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    ) {
+        self.inner.dyn_blit_buffers_from_simple_framebuffer(
+            source,
+            source_rect,
+            target_rect,
+            filter,
+            mask,
+        );
+    }
+
+    fn blit_buffers_from_multioutput_framebuffer(
+        &self,
+        source: &glium::framebuffer::MultiOutputFrameBuffer<'_>,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    ) {
+        self.inner.dyn_blit_buffers_from_multioutput_framebuffer(
+            source,
+            source_rect,
+            target_rect,
+            filter,
+            mask,
+        );
+    }
+
+    fn blit_color<S: glium::Surface>(
+        &self,
+        source_rect: &glium::Rect,
+        target: &S,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+    ) {
+        self.inner
+            .dyn_blit_color(source_rect, target, target_rect, filter);
+    }
+}
+
+/// The object-safe subset of [`glium::Surface`] used by [`CallbackSurface`].
+trait DynSurface {
+    fn dyn_clear(
+        &mut self,
+        rect: Option<&glium::Rect>,
+        color: Option<(f32, f32, f32, f32)>,
+        color_srgb: bool,
+        depth: Option<f32>,
+        stencil: Option<i32>,
+    );
+
+    fn dyn_get_dimensions(&self) -> (u32, u32);
+
+    fn dyn_get_depth_buffer_bits(&self) -> Option<u16>;
+
+    fn dyn_get_stencil_buffer_bits(&self) -> Option<u16>;
+
+    fn dyn_draw(
+        &mut self,
+        vertices: DynVertices<'_>,
+        indices: glium::index::IndicesSource<'_>,
+        program: &glium::Program,
+        uniforms: &DynUniforms<'_>,
+        draw_parameters: &glium::DrawParameters<'_>,
+    ) -> Result<(), glium::DrawError>;
+
+    fn dyn_blit_buffers_from_frame(
+        &self,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    );
+
+    fn dyn_blit_buffers_from_simple_framebuffer(
+        &self,
+        source: &glium::framebuffer::SimpleFrameBuffer<'_>,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    );
+
+    fn dyn_blit_buffers_from_multioutput_framebuffer(
+        &self,
+        source: &glium::framebuffer::MultiOutputFrameBuffer<'_>,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    );
+
+    fn dyn_blit_color(
+        &self,
+        source_rect: &glium::Rect,
+        target: &dyn DynSurface,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+    );
+}
+
+impl<T: glium::Surface> DynSurface for T {
+    fn dyn_clear(
+        &mut self,
+        rect: Option<&glium::Rect>,
+        color: Option<(f32, f32, f32, f32)>,
+        color_srgb: bool,
+        depth: Option<f32>,
+        stencil: Option<i32>,
+    ) {
+        self.clear(rect, color, color_srgb, depth, stencil);
+    }
+
+    fn dyn_get_dimensions(&self) -> (u32, u32) {
+        self.get_dimensions()
+    }
+
+    fn dyn_get_depth_buffer_bits(&self) -> Option<u16> {
+        self.get_depth_buffer_bits()
+    }
+
+    fn dyn_get_stencil_buffer_bits(&self) -> Option<u16> {
+        self.get_stencil_buffer_bits()
+    }
+
+    fn dyn_draw(
+        &mut self,
+        vertices: DynVertices<'_>,
+        indices: glium::index::IndicesSource<'_>,
+        program: &glium::Program,
+        uniforms: &DynUniforms<'_>,
+        draw_parameters: &glium::DrawParameters<'_>,
+    ) -> Result<(), glium::DrawError> {
+        self.draw(vertices, indices, program, uniforms, draw_parameters)
+    }
+
+    fn dyn_blit_buffers_from_frame(
+        &self,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    ) {
+        self.blit_buffers_from_frame(source_rect, target_rect, filter, mask);
+    }
+
+    fn dyn_blit_buffers_from_simple_framebuffer(
+        &self,
+        source: &glium::framebuffer::SimpleFrameBuffer<'_>,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    ) {
+        self.blit_buffers_from_simple_framebuffer(source, source_rect, target_rect, filter, mask);
+    }
+
+    fn dyn_blit_buffers_from_multioutput_framebuffer(
+        &self,
+        source: &glium::framebuffer::MultiOutputFrameBuffer<'_>,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    ) {
+        self.blit_buffers_from_multioutput_framebuffer(
+            source,
+            source_rect,
+            target_rect,
+            filter,
+            mask,
+        );
+    }
+
+    fn dyn_blit_color(
+        &self,
+        source_rect: &glium::Rect,
+        target: &dyn DynSurface,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+    ) {
+        self.blit_color(source_rect, &BlitTarget(target), target_rect, filter);
+    }
+}
+
+/// The target of [`glium::Surface::blit_color`] with its type erased.
+///
+/// Blitting only ever calls the `&self` methods of the target,
+/// which is all a shared reference can forward.
+struct BlitTarget<'a>(&'a dyn DynSurface);
+
+impl glium::Surface for BlitTarget<'_> {
+    fn clear(
+        &mut self,
+        _rect: Option<&glium::Rect>,
+        _color: Option<(f32, f32, f32, f32)>,
+        _color_srgb: bool,
+        _depth: Option<f32>,
+        _stencil: Option<i32>,
+    ) {
+        unreachable!("A blit target is never cleared");
+    }
+
+    fn get_dimensions(&self) -> (u32, u32) {
+        self.0.dyn_get_dimensions()
+    }
+
+    fn get_depth_buffer_bits(&self) -> Option<u16> {
+        self.0.dyn_get_depth_buffer_bits()
+    }
+
+    fn get_stencil_buffer_bits(&self) -> Option<u16> {
+        self.0.dyn_get_stencil_buffer_bits()
+    }
+
+    fn draw<'a, 'b, V, I, U>(
+        &mut self,
+        _vertices: V,
+        _indices: I,
+        _program: &glium::Program,
+        _uniforms: &U,
+        _draw_parameters: &glium::DrawParameters<'_>,
+    ) -> Result<(), glium::DrawError>
+    where
+        V: glium::vertex::MultiVerticesSource<'b>,
+        I: Into<glium::index::IndicesSource<'a>>,
+        U: glium::uniforms::Uniforms,
+    {
+        unreachable!("A blit target is never drawn to");
+    }
+
+    fn blit_buffers_from_frame(
+        &self,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    ) {
+        self.0
+            .dyn_blit_buffers_from_frame(source_rect, target_rect, filter, mask);
+    }
+
+    fn blit_buffers_from_simple_framebuffer(
+        &self,
+        source: &glium::framebuffer::SimpleFrameBuffer<'_>,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    ) {
+        self.0.dyn_blit_buffers_from_simple_framebuffer(
+            source,
+            source_rect,
+            target_rect,
+            filter,
+            mask,
+        );
+    }
+
+    fn blit_buffers_from_multioutput_framebuffer(
+        &self,
+        source: &glium::framebuffer::MultiOutputFrameBuffer<'_>,
+        source_rect: &glium::Rect,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: glium::BlitMask,
+    ) {
+        self.0.dyn_blit_buffers_from_multioutput_framebuffer(
+            source,
+            source_rect,
+            target_rect,
+            filter,
+            mask,
+        );
+    }
+
+    fn blit_color<S: glium::Surface>(
+        &self,
+        source_rect: &glium::Rect,
+        target: &S,
+        target_rect: &glium::BlitTarget,
+        filter: MagnifySamplerFilter,
+    ) {
+        self.0
+            .dyn_blit_color(source_rect, target, target_rect, filter);
+    }
+}
+
+struct DynVertices<'a>(Vec<glium::vertex::VerticesSource<'a>>);
+
+impl<'a> glium::vertex::MultiVerticesSource<'a> for DynVertices<'a> {
+    type Iterator = std::vec::IntoIter<glium::vertex::VerticesSource<'a>>;
+
+    fn iter(self) -> Self::Iterator {
+        self.0.into_iter()
+    }
+}
+
+struct DynUniforms<'a>(Vec<(String, glium::uniforms::UniformValue<'a>)>);
+
+impl glium::uniforms::Uniforms for DynUniforms<'_> {
+    fn visit_values<'b, F: FnMut(&str, glium::uniforms::UniformValue<'b>)>(&'b self, mut f: F) {
+        for (name, value) in &self.0 {
+            f(name, *value);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
 struct EguiTexture {
-    glium_texture: Rc<SrgbTexture2d>,
+    native: NativeTexture,
     options: TextureOptions,
+
+    /// Only used for [`NativeTexture::Hdr`].
+    hdr_options: HdrOptions,
 }
 
 impl EguiTexture {
-    fn new(glium_texture: Rc<SrgbTexture2d>, options: TextureOptions) -> Self {
+    fn new(native: NativeTexture, options: TextureOptions) -> Self {
         Self {
-            glium_texture,
+            native,
             options,
+            hdr_options: Default::default(),
+        }
+    }
+}
+
+/// The uniforms of our shaders.
+struct MeshUniforms<'a> {
+    screen_size: [f32; 2],
+    sampler: glium::uniforms::UniformValue<'a>,
+
+    /// See [`NativeTexture::shader_kind`].
+    texture_kind: i32,
+
+    /// Multiplier for [`NativeTexture::Hdr`] colors.
+    exposure: f32,
+
+    /// See [`crate::ToneMapping::shader_value`].
+    tone_mapping: i32,
+}
+
+impl glium::uniforms::Uniforms for MeshUniforms<'_> {
+    fn visit_values<'b, F: FnMut(&str, glium::uniforms::UniformValue<'b>)>(&'b self, mut f: F) {
+        use glium::uniforms::UniformValue;
+
+        f("u_screen_size", UniformValue::Vec2(self.screen_size));
+        f("u_sampler", self.sampler);
+        f("u_texture_kind", UniformValue::SignedInt(self.texture_kind));
+        f("u_exposure", UniformValue::Float(self.exposure));
+        f("u_tone_mapping", UniformValue::SignedInt(self.tone_mapping));
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// CPU copies of egui-managed textures, kept up to date with partial updates.
+#[derive(Default)]
+pub(crate) struct RetainedTextures {
+    textures: ahash::HashMap<egui::TextureId, egui::epaint::ImageDelta>,
+}
+
+impl RetainedTextures {
+    pub fn set(&mut self, id: egui::TextureId, image_delta: &egui::epaint::ImageDelta) {
+        let Some([x, y]) = image_delta.pos else {
+            self.textures.insert(id, image_delta.clone());
+            return;
+        };
+
+        let Some(retained) = self.textures.get_mut(&id) else {
+            return;
+        };
+        let egui::ImageData::Color(image) = &mut retained.image;
+        let egui::ImageData::Color(patch) = &image_delta.image;
+        let image = Arc::make_mut(image);
+        let width = image.width();
+        // `chunks_exact` can't make rows of zero pixels, and there is nothing to copy anyway:
+        if patch.width() > 0 {
+            for (row, patch_row) in patch.pixels.chunks_exact(patch.width()).enumerate() {
+                let start = (y + row) * width + x;
+                image.pixels[start..start + patch_row.len()].copy_from_slice(patch_row);
+            }
+        }
+        retained.options = image_delta.options;
+    }
+
+    pub fn free(&mut self, id: egui::TextureId) {
+        self.textures.remove(&id);
+    }
+
+    /// Every texture as a full [`egui::epaint::ImageDelta`], ready to upload.
+    pub fn iter(&self) -> impl Iterator<Item = (egui::TextureId, &egui::epaint::ImageDelta)> {
+        self.textures
+            .iter()
+            .map(|(&id, image_delta)| (id, image_delta))
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Measures how long the GPU spends drawing egui, without waiting for the results.
+#[derive(Default)]
+struct GpuTimer {
+    /// Queries of the frame being painted: one for each run of meshes between paint callbacks.
+    current: Vec<glium::draw_parameters::TimeElapsedQuery>,
+
+    /// Is the last query of [`Self::current`] still in use?
+    segment_open: bool,
+
+    /// Has anything been drawn with the last query of [`Self::current`]?
+    segment_used: bool,
+
+    /// Queries of earlier frames whose results we haven't picked up yet, oldest first.
+    in_flight: VecDeque<Vec<glium::draw_parameters::TimeElapsedQuery>>,
+
+    /// The newest result we have.
+    last_result: Option<Duration>,
+}
+
+impl GpuTimer {
+    /// Give up on frames that take longer than this to finish on the GPU.
+    const MAX_FRAMES_IN_FLIGHT: usize = 4;
+
+    /// Pick up the results of earlier frames that are ready.
+    fn begin_frame(&mut self) {
+        while let Some(queries) = self.in_flight.front() {
+            if !queries.iter().all(|query| query.is_ready()) {
+                break;
+            }
+            let queries = self.in_flight.pop_front().unwrap_or_default();
+            // The results are in nanoseconds:
+            let nanos = queries.into_iter().map(|query| query.get() as u64).sum();
+            self.last_result = Some(Duration::from_nanos(nanos));
+        }
+    }
+
+    fn end_frame(&mut self) {
+        self.end_segment();
+        if self.current.is_empty() {
+            return;
+        }
+        self.in_flight.push_back(std::mem::take(&mut self.current));
+        if self.in_flight.len() > Self::MAX_FRAMES_IN_FLIGHT {
+            self.in_flight.pop_front();
+        }
+    }
+
+    /// Make sure there is a query for the next draw call.
+    ///
+    /// Returns `false` if timer queries are not supported.
+    fn begin_segment(&mut self, facade: &dyn glium::backend::Facade) -> bool {
+        if self.segment_open {
+            return true;
+        }
+        match glium::draw_parameters::TimeElapsedQuery::new(facade) {
+            Ok(query) => {
+                self.current.push(query);
+                self.segment_open = true;
+                true
+            }
+            Err(err) => {
+                log::warn!("GPU timing is not available: {err}");
+                false
+            }
+        }
+    }
+
+    fn end_segment(&mut self) {
+        // A query that was never used would never become ready:
+        if self.segment_open && !self.segment_used {
+            self.current.pop();
+        } else if self.segment_open {
+            // glium only ends a query when it is next asked about it,
+            // so end it now, or the time of whatever follows (e.g. `finish`) would be counted too:
+            if let Some(query) = self.current.last() {
+                query.is_ready();
+            }
         }
+        self.segment_open = false;
+        self.segment_used = false;
+    }
+
+    fn current_query(&self) -> Option<&glium::draw_parameters::TimeElapsedQuery> {
+        self.current.last().filter(|_| self.segment_open)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use egui::{
+        epaint::{ColorImage, ImageDelta},
+        Color32, TextureId, TextureOptions,
+    };
+
+    use super::RetainedTextures;
+
+    fn image(size: [usize; 2], color: Color32) -> ColorImage {
+        ColorImage::new(size, vec![color; size[0] * size[1]])
+    }
+
+    fn retained_pixels(retained: &RetainedTextures, id: TextureId) -> Vec<Color32> {
+        let (_, image_delta) = retained
+            .iter()
+            .find(|(retained_id, _)| *retained_id == id)
+            .expect("texture should be retained");
+        let egui::ImageData::Color(image) = &image_delta.image;
+        image.pixels.clone()
+    }
+
+    #[test]
+    fn partial_delta_patches_retained_texture() {
+        let id = TextureId::Managed(1);
+        let (a, b) = (Color32::RED, Color32::BLUE);
+        let mut retained = RetainedTextures::default();
+
+        retained.set(
+            id,
+            &ImageDelta::full(image([4, 3], a), TextureOptions::LINEAR),
+        );
+        retained.set(
+            id,
+            &ImageDelta::partial([1, 1], image([2, 2], b), TextureOptions::NEAREST),
+        );
+
+        #[rustfmt::skip]
+        let expected = vec![
+            a, a, a, a,
+            a, b, b, a,
+            a, b, b, a,
+        ];
+        assert_eq!(retained_pixels(&retained, id), expected);
+
+        let (_, image_delta) = retained.iter().next().unwrap();
+        assert_eq!(image_delta.pos, None);
+        assert_eq!(image_delta.options, TextureOptions::NEAREST);
+    }
+
+    #[test]
+    fn empty_partial_delta_is_ignored() {
+        let id = TextureId::Managed(1);
+        let mut retained = RetainedTextures::default();
+
+        retained.set(
+            id,
+            &ImageDelta::full(image([2, 2], Color32::RED), TextureOptions::LINEAR),
+        );
+        retained.set(
+            id,
+            &ImageDelta::partial([1, 1], image([0, 0], Color32::BLUE), TextureOptions::LINEAR),
+        );
+
+        assert_eq!(retained_pixels(&retained, id), vec![Color32::RED; 4]);
+    }
+
+    #[test]
+    fn free_forgets_texture() {
+        let (kept, freed) = (TextureId::Managed(1), TextureId::Managed(2));
+        let mut retained = RetainedTextures::default();
+
+        for id in [kept, freed] {
+            retained.set(
+                id,
+                &ImageDelta::full(image([1, 1], Color32::RED), TextureOptions::LINEAR),
+            );
+        }
+        retained.free(freed);
+
+        let ids: Vec<TextureId> = retained.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![kept]);
     }
 }
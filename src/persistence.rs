@@ -0,0 +1,188 @@
+//! Saving [`egui::Memory`] and the window geometry between runs.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// How often [`Persistence::autosave`] saves by default.
+pub(crate) const DEFAULT_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Everything we write to disk.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct PersistedState {
+    window: WindowGeometry,
+    memory: egui::Memory,
+}
+
+/// The geometry of the root window, in physical pixels.
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct WindowGeometry {
+    inner_size: Option<[u32; 2]>,
+    outer_position: Option<[i32; 2]>,
+    maximized: bool,
+}
+
+impl WindowGeometry {
+    fn from_window(window: &winit::window::Window) -> Self {
+        let inner_size = window.inner_size();
+        Self {
+            inner_size: Some([inner_size.width, inner_size.height]),
+            outer_position: window
+                .outer_position()
+                .ok()
+                .map(|position| [position.x, position.y]),
+            maximized: window.is_maximized(),
+        }
+    }
+
+    fn apply_to_window(&self, window: &winit::window::Window) {
+        if let Some([width, height]) = self.inner_size {
+            let _ = window.request_inner_size(winit::dpi::PhysicalSize::new(width, height));
+        }
+        if let Some([x, y]) = self.outer_position {
+            window.set_outer_position(winit::dpi::PhysicalPosition::new(x, y));
+        }
+        window.set_maximized(self.maximized);
+    }
+}
+
+/// Saves one [`egui::Context`], also when dropped.
+pub(crate) struct Persistence {
+    egui_ctx: egui::Context,
+
+    /// `None` if we couldn't find an app data directory.
+    path: Option<PathBuf>,
+
+    autosave_interval: Duration,
+    last_save: Instant,
+
+    /// As of the last call to [`Self::update_window_geometry`].
+    window: WindowGeometry,
+}
+
+impl Persistence {
+    pub fn new(egui_ctx: egui::Context, app_id: &str, autosave_interval: Duration) -> Self {
+        let path = data_file(app_id, "app.ron");
+
+        Self {
+            egui_ctx,
+            path,
+            autosave_interval,
+            last_save: Instant::now(),
+            window: Default::default(),
+        }
+    }
+
+    /// Load what was saved last time, and apply it to the context and window.
+    pub fn restore(&mut self, window: &winit::window::Window) {
+        let Some(state) = self.path.as_deref().and_then(read_ron::<PersistedState>) else {
+            return;
+        };
+
+        self.egui_ctx.memory_mut(|memory| *memory = state.memory);
+        state.window.apply_to_window(window);
+        self.window = state.window;
+    }
+
+    /// Remember the window geometry for the next save.
+    ///
+    /// Nothing is remembered while the window is minimized, since it then has no useful size.
+    pub fn update_window_geometry(&mut self, window: &winit::window::Window) {
+        if window.is_minimized() != Some(true) {
+            self.window = WindowGeometry::from_window(window);
+        }
+    }
+
+    /// Save if it has been long enough since the last save.
+    pub fn autosave(&mut self) {
+        if self.last_save.elapsed() >= self.autosave_interval {
+            self.save();
+        }
+    }
+
+    pub fn save(&mut self) {
+        self.last_save = Instant::now();
+
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        self.egui_ctx.memory(|memory| {
+            let state = PersistedStateRef {
+                window: &self.window,
+                memory,
+            };
+            write_ron(path, &state);
+        });
+    }
+}
+
+impl Drop for Persistence {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
+
+/// Same as [`PersistedState`], but borrowed, so we don't have to clone [`egui::Memory`] to save it.
+#[derive(serde::Serialize)]
+struct PersistedStateRef<'a> {
+    window: &'a WindowGeometry,
+    memory: &'a egui::Memory,
+}
+
+// ----------------------------------------------------------------------------
+
+/// A file called `name` in the app data directory of the platform.
+///
+/// `None` if there is no such directory, in which case nothing will be saved.
+pub(crate) fn data_file(app_id: &str, name: &str) -> Option<PathBuf> {
+    let path =
+        directories::ProjectDirs::from("", "", app_id).map(|dirs| dirs.data_dir().join(name));
+    if path.is_none() {
+        log::warn!("Failed to find an app data directory; {name} will not be saved");
+    }
+    path
+}
+
+/// `None` if the file doesn't exist yet, or couldn't be read.
+pub(crate) fn read_ron<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let ron = match std::fs::read_to_string(path) {
+        Ok(ron) => ron,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(err) => {
+            log::warn!("Failed to read {}: {err}", path.display());
+            return None;
+        }
+    };
+    match ron::from_str(&ron) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            log::warn!("Failed to parse {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+/// Failures are logged.
+pub(crate) fn write_ron<T: serde::Serialize>(path: &Path, value: &T) {
+    let ron = match ron::ser::to_string_pretty(value, Default::default()) {
+        Ok(ron) => ron,
+        Err(err) => {
+            log::warn!("Failed to serialize {}: {err}", path.display());
+            return;
+        }
+    };
+
+    if let Some(dir) = path.parent() {
+        if let Err(err) = std::fs::create_dir_all(dir) {
+            log::warn!("Failed to create {}: {err}", dir.display());
+            return;
+        }
+    }
+    if let Err(err) = std::fs::write(path, ron) {
+        log::warn!("Failed to write {}: {err}", path.display());
+    }
+}
@@ -0,0 +1,302 @@
+//! A ready-made [`winit`] event loop for simple single-window apps,
+//! for when you don't need to control it yourself.
+//!
+//! ```no_run
+//! struct MyApp;
+//!
+//! impl egui_glium::runner::App for MyApp {
+//!     fn ui(&mut self, ctx: &egui::Context) {
+//!         egui::CentralPanel::default().show(ctx, |ui| {
+//!             ui.heading("Hello World!");
+//!         });
+//!     }
+//! }
+//!
+//! egui_glium::runner::run(Default::default(), MyApp).unwrap();
+//! ```
+
+use glium::{backend::glutin::SimpleWindowBuilder, glutin::surface::WindowSurface, Surface as _};
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+use crate::EguiGlium;
+
+/// The user events of our event loop.
+#[cfg(feature = "accesskit")]
+type UserEvent = egui_winit::accesskit_winit::Event;
+
+/// The user events of our event loop.
+#[cfg(not(feature = "accesskit"))]
+type UserEvent = ();
+
+/// Implement this for your app and pass it to [`run`].
+pub trait App {
+    /// Called once, after the window has been created and before it is first shown.
+    ///
+    /// Use it to set up things that need the display or the [`crate::Painter`],
+    /// e.g. to register native textures with [`crate::Painter::register_native_texture`].
+    ///
+    /// `settings` are the ones the window was created with,
+    /// which may have been restored from the last run. See [`WindowSettings`].
+    fn setup(
+        &mut self,
+        _egui_glium: &mut EguiGlium,
+        _display: &glium::Display<WindowSurface>,
+        _settings: &WindowSettings,
+    ) {
+    }
+
+    /// Called each time the UI needs repainting.
+    fn ui(&mut self, ctx: &egui::Context);
+
+    /// What to clear the window to, if it should change from [`WindowSettings::clear_color`].
+    ///
+    /// Called every frame, after [`Self::ui`].
+    /// The last color returned is the one handed back in [`Self::on_exit`].
+    fn clear_color(&self) -> Option<egui::Rgba> {
+        None
+    }
+
+    /// Called after the window has been cleared, before egui is painted.
+    ///
+    /// Draw things that should appear behind egui here.
+    fn paint_before(
+        &mut self,
+        _display: &glium::Display<WindowSurface>,
+        _frame: &mut glium::Frame,
+    ) {
+    }
+
+    /// Called after egui has been painted, before the frame is shown.
+    ///
+    /// Draw things that should appear on top of egui here.
+    fn paint_after(&mut self, _display: &glium::Display<WindowSurface>, _frame: &mut glium::Frame) {
+    }
+
+    /// Called once before the event loop exits,
+    /// with the window settings as they are now.
+    ///
+    /// With the `persistence` feature they have already been saved.
+    /// Without it, store them wherever you like, and pass them to [`run`] on the next start
+    /// to get the same window back.
+    fn on_exit(&mut self, _settings: &WindowSettings) {}
+}
+
+/// The window settings [`run`] starts with, and which are handed back in [`App::on_exit`].
+///
+/// With the `persistence` feature, the settings are saved on exit,
+/// in the app data directory of the platform under the name of the executable.
+/// On the next start, the saved `inner_size` and `clear_color` win over the ones passed to [`run`],
+/// while the `title` always comes from [`run`].
+/// The position and maximized state of the window are restored too,
+/// by the [`EguiGlium`] the runner creates.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "persistence", serde(default))]
+pub struct WindowSettings {
+    pub title: String,
+
+    /// Size of the inside of the window, in physical pixels.
+    pub inner_size: [u32; 2],
+
+    /// What to clear the window to before painting.
+    ///
+    /// The app can change it while running with [`App::clear_color`].
+    pub clear_color: egui::Rgba,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            title: "egui_glium app".to_owned(),
+            inner_size: [800, 600],
+            clear_color: egui::Rgba::from_rgb(0.1, 0.3, 0.2),
+        }
+    }
+}
+
+/// Create a window and run `app` in it until the window is closed.
+///
+/// Closing the window goes through egui, so the app can cancel it with
+/// [`egui::ViewportCommand::CancelClose`], or close the window itself with
+/// [`egui::ViewportCommand::Close`].
+pub fn run(settings: WindowSettings, app: impl App) -> Result<(), winit::error::EventLoopError> {
+    #[cfg(feature = "persistence")]
+    let settings = match settings_path().and_then(|path| crate::persistence::read_ron(&path)) {
+        Some(saved) => WindowSettings {
+            title: settings.title,
+            ..saved
+        },
+        None => settings,
+    };
+
+    let event_loop = EventLoop::<UserEvent>::with_user_event().build()?;
+    let mut runner = Runner {
+        settings,
+        app,
+        window: None,
+        #[cfg(feature = "accesskit")]
+        event_loop_proxy: event_loop.create_proxy(),
+    };
+    event_loop.run_app(&mut runner)
+}
+
+struct Runner<A> {
+    settings: WindowSettings,
+    app: A,
+
+    /// Created on the first [`ApplicationHandler::resumed`].
+    window: Option<RunnerWindow>,
+
+    #[cfg(feature = "accesskit")]
+    event_loop_proxy: winit::event_loop::EventLoopProxy<UserEvent>,
+}
+
+/// `window` is declared last so it is dropped last: the display and egui draw to it.
+struct RunnerWindow {
+    egui_glium: EguiGlium,
+    display: glium::Display<WindowSurface>,
+    window: Window,
+}
+
+impl<A: App> Runner<A> {
+    fn redraw(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(RunnerWindow {
+            egui_glium,
+            display,
+            window,
+        }) = &mut self.window
+        else {
+            return;
+        };
+
+        egui_glium.run(window, |ctx| self.app.ui(ctx));
+
+        if egui_glium.should_close() {
+            event_loop.exit();
+            return;
+        }
+
+        if let Some(clear_color) = self.app.clear_color() {
+            self.settings.clear_color = clear_color;
+        }
+
+        let mut frame = display.draw();
+        let color = self.settings.clear_color;
+        frame.clear_color(color[0], color[1], color[2], color[3]);
+        self.app.paint_before(display, &mut frame);
+        egui_glium.paint(display, &mut frame);
+        self.app.paint_after(display, &mut frame);
+        if let Err(err) = frame.finish() {
+            log::error!("Failed to swap buffers: {err}");
+        }
+    }
+}
+
+impl<A: App> ApplicationHandler<UserEvent> for Runner<A> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+
+        let [width, height] = self.settings.inner_size;
+        // Hidden until set up, since AccessKit must be initialized before the window is shown:
+        let (window, display) = SimpleWindowBuilder::new()
+            .set_window_builder(
+                Window::default_attributes()
+                    .with_resizable(true)
+                    .with_visible(false),
+            )
+            .with_inner_size(width, height)
+            .with_title(&self.settings.title)
+            .build(event_loop);
+        let builder = EguiGlium::builder();
+        #[cfg(feature = "accesskit")]
+        let builder = builder.with_accesskit(event_loop, self.event_loop_proxy.clone());
+        let mut egui_glium = builder
+            .build(&display, &window, event_loop)
+            .unwrap_or_else(|err| panic!("{err}"));
+
+        self.app.setup(&mut egui_glium, &display, &self.settings);
+
+        window.set_visible(true);
+        window.request_redraw();
+        self.window = Some(RunnerWindow {
+            egui_glium,
+            display,
+            window,
+        });
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        match &event {
+            WindowEvent::Destroyed => event_loop.exit(),
+            WindowEvent::Resized(new_size) => {
+                self.settings.inner_size = [new_size.width, new_size.height];
+                if let Some(window) = &self.window {
+                    window.display.resize((*new_size).into());
+                }
+            }
+            WindowEvent::RedrawRequested => self.redraw(event_loop),
+            _ => {}
+        }
+
+        if let Some(window) = &mut self.window {
+            let event_response = window.egui_glium.on_event(&window.window, &event);
+            if event_response.repaint {
+                window.window.request_redraw();
+            }
+        }
+    }
+
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: winit::event::DeviceId,
+        event: winit::event::DeviceEvent,
+    ) {
+        if let Some(window) = &mut self.window {
+            let event_response = window.egui_glium.on_device_event(&event);
+            if event_response.repaint {
+                window.window.request_redraw();
+            }
+        }
+    }
+
+    #[cfg(feature = "accesskit")]
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: UserEvent) {
+        if let Some(window) = &mut self.window {
+            let event_response = window.egui_glium.on_accesskit_event(&event.window_event);
+            if event_response.repaint {
+                window.window.request_redraw();
+            }
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(window) = &self.window {
+            window
+                .egui_glium
+                .schedule_repaint(event_loop, &window.window);
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        #[cfg(feature = "persistence")]
+        if let Some(path) = settings_path() {
+            crate::persistence::write_ron(&path, &self.settings);
+        }
+
+        self.app.on_exit(&self.settings);
+    }
+}
+
+/// Where [`WindowSettings`] are saved, next to what [`EguiGlium`] saves.
+#[cfg(feature = "persistence")]
+fn settings_path() -> Option<std::path::PathBuf> {
+    crate::persistence::data_file(&crate::default_app_id()?, "window_settings.ron")
+}
@@ -1,6 +1,9 @@
 #version 140
 
 uniform sampler2D u_sampler;
+uniform int u_texture_kind; // See `NativeTexture::shader_kind`
+uniform float u_exposure; // Multiplier for HDR textures
+uniform int u_tone_mapping; // See `ToneMapping::shader_value`
 in vec4 v_rgba_gamma;
 in vec2 v_tc;
 out vec4 f_color;
@@ -23,9 +26,48 @@ vec4 gamma_from_linear_rgba(vec4 linear_rgba) {
     return vec4(srgb_from_linear(linear_rgba.rgb) / 255.0, linear_rgba.a);
 }
 
+// Values of `u_texture_kind`:
+const int KIND_SRGB = 0;
+const int KIND_LINEAR = 1;
+const int KIND_GRAY = 2;
+const int KIND_ALPHA = 3;
+const int KIND_HDR = 4;
+
+// Values of `u_tone_mapping`:
+const int TONE_MAPPING_CLAMP = 0;
+const int TONE_MAPPING_REINHARD = 1;
+const int TONE_MAPPING_ACES = 2;
+
+// 0-1 linear  from  0-inf linear
+vec3 tone_map(vec3 rgb) {
+    if (u_tone_mapping == TONE_MAPPING_REINHARD) {
+        rgb = rgb / (vec3(1.0) + rgb);
+    } else if (u_tone_mapping == TONE_MAPPING_ACES) {
+        // Krzysztof Narkowicz's fit of the ACES filmic curve:
+        rgb = (rgb * (2.51 * rgb + vec3(0.03))) / (rgb * (2.43 * rgb + vec3(0.59)) + vec3(0.14));
+    }
+    return clamp(rgb, 0.0, 1.0);
+}
+
 void main() {
-    // The texture is set up with `SRGB8_ALPHA8`
-    vec4 texture_in_gamma = gamma_from_linear_rgba(texture(u_sampler, v_tc));
+    vec4 texel = texture(u_sampler, v_tc);
+
+    vec4 texture_in_gamma;
+    if (u_texture_kind == KIND_ALPHA) {
+        // White, with premultiplied alpha:
+        texture_in_gamma = vec4(texel.r);
+    } else if (u_texture_kind == KIND_HDR) {
+        // Tone mapping isn't linear, so it must be applied to the color without premultiplied alpha:
+        float alpha = clamp(texel.a, 0.0, 1.0);
+        vec3 rgb = alpha > 0.0 ? max(texel.rgb, vec3(0.0)) / alpha : vec3(0.0);
+        texture_in_gamma = alpha * gamma_from_linear_rgba(vec4(tone_map(u_exposure * rgb), 1.0));
+    } else {
+        if (u_texture_kind == KIND_GRAY) {
+            texel = vec4(texel.rrr, 1.0);
+        }
+        // `SRGB8_ALPHA8` textures are decoded to linear when sampled, the others are linear already:
+        texture_in_gamma = gamma_from_linear_rgba(texel);
+    }
 
     // Multiply vertex color with texture color (in gamma space).
     f_color = v_rgba_gamma * texture_in_gamma;
@@ -0,0 +1,101 @@
+use crate::{Error, Painter};
+
+/// Run egui for a UI and paint it offscreen, without any window.
+///
+/// A fresh [`egui::Context`] is used each time, so configure style and fonts from within `run_ui`.
+///
+/// This is useful for snapshot tests of widgets, e.g. on a CI machine without a GPU
+/// using Mesa's `llvmpipe`.
+///
+/// The UI is run a few times until egui stops asking for an immediate repaint,
+/// so that things like window sizing passes have settled.
+/// Time advances by a fixed step each pass, which keeps the result deterministic.
+///
+/// `facade` can be any [`glium::backend::Facade`], e.g. an `Rc<glium::backend::Context>`
+/// wrapping a headless or surfaceless OpenGL context.
+/// `tests/snapshot.rs` in the repository shows how to create one with EGL.
+///
+/// The returned image has premultiplied alpha on a transparent background.
+pub fn snapshot(
+    facade: &dyn glium::backend::Facade,
+    size_in_points: egui::Vec2,
+    pixels_per_point: f32,
+    run_ui: impl FnMut(&egui::Context),
+) -> Result<egui::ColorImage, Error> {
+    let mut painter = Painter::try_new(facade)?;
+    snapshot_with_painter(
+        &mut painter,
+        facade,
+        size_in_points,
+        pixels_per_point,
+        run_ui,
+    )
+}
+
+/// Like [`snapshot`], but paints with the given [`Painter`].
+///
+/// Use this to show native textures, registered with [`Painter::register_native_texture`] beforehand.
+pub fn snapshot_with_painter(
+    painter: &mut Painter,
+    facade: &dyn glium::backend::Facade,
+    size_in_points: egui::Vec2,
+    pixels_per_point: f32,
+    mut run_ui: impl FnMut(&egui::Context),
+) -> Result<egui::ColorImage, Error> {
+    /// Upper limit on how many times we run the UI before painting.
+    const MAX_PASSES: usize = 4;
+
+    let egui_ctx = egui::Context::default();
+
+    let mut textures_delta = egui::TexturesDelta::default();
+    let mut shapes = Vec::new();
+    let mut output_pixels_per_point = pixels_per_point;
+
+    for pass in 0..MAX_PASSES {
+        let predicted_dt = 1.0 / 60.0;
+        let mut raw_input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, size_in_points)),
+            max_texture_side: Some(painter.max_texture_side()),
+            time: Some(pass as f64 * predicted_dt as f64),
+            predicted_dt,
+            focused: true,
+            ..Default::default()
+        };
+        raw_input
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(pixels_per_point);
+
+        let full_output = egui_ctx.run(raw_input, &mut run_ui);
+
+        textures_delta.append(full_output.textures_delta);
+        shapes = full_output.shapes;
+        output_pixels_per_point = full_output.pixels_per_point;
+
+        let wants_repaint = full_output
+            .viewport_output
+            .get(&egui::ViewportId::ROOT)
+            .is_some_and(|viewport_output| viewport_output.repaint_delay.is_zero());
+        if !wants_repaint {
+            break;
+        }
+    }
+
+    let clipped_primitives = egui_ctx.tessellate(shapes, output_pixels_per_point);
+
+    let size_in_pixels = [
+        (size_in_points.x * output_pixels_per_point).round() as u32,
+        (size_in_points.y * output_pixels_per_point).round() as u32,
+    ];
+    let texture = painter.try_paint_to_texture(
+        facade,
+        size_in_pixels,
+        output_pixels_per_point,
+        &clipped_primitives,
+        &textures_delta,
+    )?;
+
+    let surface = glium::framebuffer::SimpleFrameBuffer::new(facade, &texture)?;
+    painter.read_screen_rgba(facade, &surface)
+}
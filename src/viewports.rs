@@ -0,0 +1,630 @@
+//! Support for egui's multiple viewports, where each viewport is its own native window.
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use egui::{
+    DeferredViewportUiCallback, ImmediateViewport, ViewportBuilder, ViewportClass, ViewportId,
+    ViewportIdMap, ViewportIdPair, ViewportIdSet, ViewportInfo, ViewportOutput,
+};
+use glium::{backend::glutin::SimpleWindowBuilder, glutin::surface::WindowSurface, Surface as _};
+use winit::{
+    event_loop::{ActiveEventLoop, ControlFlow},
+    window::WindowId,
+};
+
+use crate::{actions::ActionsRequested, painter::RetainedTextures, EventResponse, Painter};
+
+/// Convenience wrapper for using [`egui`] with multiple viewports from a [`glium`] app.
+///
+/// Every viewport gets its own native window, [`glium::Display`] and [`Painter`],
+/// while they all share one [`egui::Context`].
+/// Both [`egui::Context::show_viewport_deferred`] and [`egui::Context::show_viewport_immediate`]
+/// are supported.
+///
+/// Since the painters don't share an OpenGL context, a CPU copy of every egui-managed texture
+/// is kept, so that windows opened later can be given the full set.
+///
+/// Forward every [`winit::event::WindowEvent`] to [`Self::on_window_event`],
+/// call [`Self::run_and_paint`] on [`winit::event::WindowEvent::RedrawRequested`],
+/// and [`Self::about_to_wait`] from [`winit::application::ApplicationHandler::about_to_wait`].
+///
+/// A new immediate viewport is shown one frame late,
+/// since its window can only be created once we are back in the event loop.
+///
+/// If a window can't be set up for painting, or painting it fails, the error is logged
+/// and that viewport is skipped, so one broken window doesn't take down the app.
+pub struct EguiGliumViewports {
+    egui_ctx: egui::Context,
+    shared: Rc<RefCell<SharedState>>,
+
+    /// Set when egui asked to close the root viewport and nobody canceled it.
+    should_close: bool,
+}
+
+impl EguiGliumViewports {
+    /// The root window is created on the first call to [`Self::resumed`].
+    pub fn new(egui_ctx: egui::Context, root_builder: ViewportBuilder) -> Self {
+        egui_ctx.set_embed_viewports(false);
+
+        let mut shared = SharedState {
+            egui_ctx: egui_ctx.clone(),
+            viewports: Default::default(),
+            viewport_from_window: Default::default(),
+            textures: Default::default(),
+        };
+        shared.initialize_or_update_viewport(
+            ViewportIdPair::ROOT,
+            ViewportClass::Root,
+            root_builder,
+            None,
+        );
+        let shared = Rc::new(RefCell::new(shared));
+
+        let weak_shared = Rc::downgrade(&shared);
+        egui::Context::set_immediate_viewport_renderer(move |egui_ctx, immediate_viewport| {
+            if let Some(shared) = weak_shared.upgrade() {
+                render_immediate_viewport(egui_ctx, &shared, immediate_viewport);
+            } else {
+                let mut viewport_ui_cb = immediate_viewport.viewport_ui_cb;
+                viewport_ui_cb(egui_ctx);
+            }
+        });
+
+        Self {
+            egui_ctx,
+            shared,
+            should_close: false,
+        }
+    }
+
+    pub fn egui_ctx(&self) -> &egui::Context {
+        &self.egui_ctx
+    }
+
+    /// Which viewport the given window belongs to, if any.
+    pub fn viewport_id(&self, window_id: WindowId) -> Option<ViewportId> {
+        self.shared
+            .borrow()
+            .viewport_from_window
+            .get(&window_id)
+            .copied()
+    }
+
+    /// Call from [`winit::application::ApplicationHandler::resumed`].
+    ///
+    /// Creates the windows of all viewports that don't have one yet.
+    pub fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        self.shared.borrow_mut().initialize_windows(event_loop);
+    }
+
+    /// Forward a window event to the viewport it belongs to.
+    ///
+    /// Resizes the display of that viewport, and requests a redraw if egui wants one.
+    ///
+    /// Closing a window is reported to egui with [`egui::ViewportEvent::Close`].
+    /// For the root window, check [`Self::should_close`] after [`Self::run_and_paint`].
+    pub fn on_window_event(
+        &mut self,
+        window_id: WindowId,
+        event: &winit::event::WindowEvent,
+    ) -> EventResponse {
+        use winit::event::WindowEvent;
+
+        let mut shared = self.shared.borrow_mut();
+        let Some(&viewport_id) = shared.viewport_from_window.get(&window_id) else {
+            return EventResponse {
+                consumed: false,
+                repaint: false,
+            };
+        };
+        let Some(viewport) = shared.viewports.get_mut(&viewport_id) else {
+            return EventResponse {
+                consumed: false,
+                repaint: false,
+            };
+        };
+        let Some(window) = &mut viewport.window else {
+            return EventResponse {
+                consumed: false,
+                repaint: false,
+            };
+        };
+
+        match event {
+            WindowEvent::Resized(new_size) => {
+                window.display.resize((*new_size).into());
+            }
+            WindowEvent::CloseRequested => {
+                viewport.info.events.push(egui::ViewportEvent::Close);
+            }
+            _ => {}
+        }
+
+        let mut event_response = window.egui_winit.on_window_event(&window.window, event);
+        if matches!(event, WindowEvent::CloseRequested) {
+            event_response.repaint = true;
+        }
+        if event_response.repaint {
+            shared.request_redraw(viewport_id);
+        }
+        event_response
+    }
+
+    /// Did egui ask to close the root viewport during the last call to [`Self::run_and_paint`]
+    /// for the root window?
+    ///
+    /// This happens on [`egui::ViewportCommand::Close`], or when the root window was asked to close
+    /// and the app did not respond with [`egui::ViewportCommand::CancelClose`].
+    ///
+    /// It is up to you to exit the event loop.
+    pub fn should_close(&self) -> bool {
+        self.should_close
+    }
+
+    /// Run egui for the viewport of the given window and paint it.
+    ///
+    /// `run_ui` is only used for the root viewport.
+    /// Deferred viewports use the callback they were shown with,
+    /// and immediate viewports are painted together with their parent.
+    pub fn run_and_paint(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        mut run_ui: impl FnMut(&egui::Context),
+    ) {
+        let Some(viewport_id) = self.viewport_id(window_id) else {
+            return;
+        };
+
+        let (raw_input, viewport_ui_cb) = {
+            let mut shared = self.shared.borrow_mut();
+            let Some(viewport) = shared.viewports.get(&viewport_id) else {
+                return;
+            };
+            if viewport.class == ViewportClass::Immediate {
+                // Only the parent can show an immediate viewport:
+                let parent_id = viewport.ids.parent;
+                shared.request_redraw(parent_id);
+                return;
+            }
+            let viewport_ui_cb = viewport.viewport_ui_cb.clone();
+            let Some(raw_input) = shared.take_egui_input(viewport_id) else {
+                return;
+            };
+            (raw_input, viewport_ui_cb)
+        };
+
+        let close_requested = raw_input.viewport().close_requested();
+
+        // No borrow of the shared state may be held here, since immediate viewports need it:
+        let full_output = self.egui_ctx.run(raw_input, |egui_ctx| {
+            if let Some(viewport_ui_cb) = &viewport_ui_cb {
+                viewport_ui_cb(egui_ctx);
+            } else {
+                run_ui(egui_ctx);
+            }
+        });
+
+        if viewport_id == ViewportId::ROOT {
+            let canceled = full_output
+                .viewport_output
+                .get(&ViewportId::ROOT)
+                .is_some_and(|output| {
+                    output
+                        .commands
+                        .contains(&egui::ViewportCommand::CancelClose)
+                });
+            if close_requested && canceled {
+                log::debug!("Closing of root viewport canceled with ViewportCommand::CancelClose");
+            }
+            self.should_close = close_requested && !canceled;
+        }
+
+        let mut shared = self.shared.borrow_mut();
+        shared.handle_output(viewport_id, full_output);
+        shared.initialize_windows(event_loop);
+    }
+
+    /// Call from [`winit::application::ApplicationHandler::about_to_wait`].
+    ///
+    /// Requests redraws of the viewports egui wants repainted,
+    /// and sets the [`ControlFlow`] to wake up for the next one.
+    pub fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let mut shared = self.shared.borrow_mut();
+        shared.initialize_windows(event_loop);
+
+        let now = Instant::now();
+        let mut next_repaint = None;
+        for viewport in shared.viewports.values_mut() {
+            let Some(repaint_at) = viewport.repaint_at else {
+                continue;
+            };
+            if repaint_at <= now {
+                viewport.repaint_at = None;
+                if let Some(window) = &viewport.window {
+                    window.window.request_redraw();
+                }
+            } else {
+                next_repaint =
+                    Some(next_repaint.map_or(repaint_at, |t: Instant| t.min(repaint_at)));
+            }
+        }
+
+        event_loop.set_control_flow(match next_repaint {
+            Some(repaint_at) => ControlFlow::WaitUntil(repaint_at),
+            None => ControlFlow::Wait,
+        });
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+struct SharedState {
+    egui_ctx: egui::Context,
+    viewports: ViewportIdMap<Viewport>,
+    viewport_from_window: ahash::HashMap<WindowId, ViewportId>,
+
+    /// CPU copies of all egui-managed textures, so they can be uploaded to new windows.
+    textures: RetainedTextures,
+}
+
+struct Viewport {
+    ids: ViewportIdPair,
+    class: ViewportClass,
+    builder: ViewportBuilder,
+    info: ViewportInfo,
+    viewport_ui_cb: Option<Arc<DeferredViewportUiCallback>>,
+
+    /// `None` until the window has been created.
+    window: Option<ViewportWindow>,
+
+    /// Set if we failed to set up painting for the window, so we don't keep trying.
+    failed: bool,
+
+    actions_requested: ActionsRequested,
+
+    /// When egui wants this viewport to be repainted.
+    repaint_at: Option<Instant>,
+}
+
+/// The fields are dropped in order, so the window outlives everything drawing to it.
+struct ViewportWindow {
+    painter: Painter,
+    egui_winit: egui_winit::State,
+    display: glium::Display<WindowSurface>,
+    window: winit::window::Window,
+}
+
+impl SharedState {
+    fn initialize_or_update_viewport(
+        &mut self,
+        ids: ViewportIdPair,
+        class: ViewportClass,
+        builder: ViewportBuilder,
+        viewport_ui_cb: Option<Arc<DeferredViewportUiCallback>>,
+    ) -> &mut Viewport {
+        let viewport = self.viewports.entry(ids.this).or_insert_with(|| {
+            log::debug!("Creating new viewport {:?} ({:?})", ids.this, builder.title);
+            Viewport {
+                ids,
+                class,
+                builder: builder.clone(),
+                info: Default::default(),
+                viewport_ui_cb: None,
+                window: None,
+                failed: false,
+                actions_requested: Default::default(),
+                repaint_at: None,
+            }
+        });
+
+        viewport.ids.parent = ids.parent;
+        viewport.class = class;
+        viewport.viewport_ui_cb = viewport_ui_cb;
+
+        let (delta_commands, recreate) = viewport.builder.patch(builder);
+        if recreate {
+            if let Some(window) = viewport.window.take() {
+                log::debug!("Recreating window for viewport {:?}", ids.this);
+                self.viewport_from_window.remove(&window.window.id());
+            }
+        } else if let Some(window) = &viewport.window {
+            viewport.actions_requested.process_viewport_commands(
+                &self.egui_ctx,
+                &mut viewport.info,
+                delta_commands,
+                &window.window,
+            );
+        }
+
+        viewport
+    }
+
+    fn initialize_windows(&mut self, event_loop: &ActiveEventLoop) {
+        for (&viewport_id, viewport) in &mut self.viewports {
+            if viewport.window.is_some() || viewport.failed {
+                continue;
+            }
+
+            let window_attributes = egui_winit::create_winit_window_attributes(
+                &self.egui_ctx,
+                viewport.builder.clone(),
+            );
+            let (window, display) = SimpleWindowBuilder::new()
+                .set_window_builder(window_attributes)
+                .build(event_loop);
+            egui_winit::apply_viewport_builder_to_window(
+                &self.egui_ctx,
+                &window,
+                &viewport.builder,
+            );
+            egui_winit::update_viewport_info(&mut viewport.info, &self.egui_ctx, &window, true);
+
+            let mut painter = match Painter::try_new(&display) {
+                Ok(painter) => painter,
+                Err(err) => {
+                    log::error!("Failed to create painter for viewport {viewport_id:?}: {err}");
+                    viewport.failed = true;
+                    continue;
+                }
+            };
+            for (id, image_delta) in self.textures.iter() {
+                if let Err(err) = painter.try_set_texture(&display, id, image_delta) {
+                    log::error!(
+                        "Failed to upload texture {id:?} to viewport {viewport_id:?}: {err}"
+                    );
+                }
+            }
+
+            let egui_winit = egui_winit::State::new(
+                self.egui_ctx.clone(),
+                viewport_id,
+                event_loop,
+                Some(window.scale_factor() as f32),
+                event_loop.system_theme(),
+                Some(painter.max_texture_side()),
+            );
+
+            window.request_redraw();
+            self.viewport_from_window.insert(window.id(), viewport_id);
+            viewport.window = Some(ViewportWindow {
+                painter,
+                egui_winit,
+                display,
+                window,
+            });
+        }
+    }
+
+    fn request_redraw(&self, viewport_id: ViewportId) {
+        if let Some(window) = self
+            .viewports
+            .get(&viewport_id)
+            .and_then(|viewport| viewport.window.as_ref())
+        {
+            window.window.request_redraw();
+        }
+    }
+
+    fn viewport_infos(&self) -> ViewportIdMap<ViewportInfo> {
+        self.viewports
+            .iter()
+            .map(|(&id, viewport)| (id, viewport.info.clone()))
+            .collect()
+    }
+
+    /// Returns `None` if the viewport has no window yet.
+    fn take_egui_input(&mut self, viewport_id: ViewportId) -> Option<egui::RawInput> {
+        let viewport = self.viewports.get_mut(&viewport_id)?;
+        let window = viewport.window.as_mut()?;
+
+        egui_winit::update_viewport_info(&mut viewport.info, &self.egui_ctx, &window.window, false);
+        let mut raw_input = window.egui_winit.take_egui_input(&window.window);
+
+        raw_input.viewports = self.viewport_infos();
+
+        // The events should only be delivered once:
+        if let Some(viewport) = self.viewports.get_mut(&viewport_id) {
+            viewport.info.events.clear();
+        }
+
+        Some(raw_input)
+    }
+
+    fn handle_output(&mut self, viewport_id: ViewportId, full_output: egui::FullOutput) {
+        let egui::FullOutput {
+            platform_output,
+            textures_delta,
+            shapes,
+            pixels_per_point,
+            viewport_output,
+        } = full_output;
+
+        for (id, image_delta) in &textures_delta.set {
+            self.set_texture(*id, image_delta);
+        }
+
+        if let Some(viewport) = self.viewports.get_mut(&viewport_id) {
+            if let Some(window) = &mut viewport.window {
+                window
+                    .egui_winit
+                    .handle_platform_output(&window.window, platform_output);
+                viewport
+                    .actions_requested
+                    .handle_clipboard(&mut window.egui_winit);
+
+                let clipped_primitives = self.egui_ctx.tessellate(shapes, pixels_per_point);
+                let clear_color = self
+                    .egui_ctx
+                    .style()
+                    .visuals
+                    .panel_fill
+                    .to_normalized_gamma_f32();
+
+                let mut target = window.display.draw();
+                target.clear_color(
+                    clear_color[0],
+                    clear_color[1],
+                    clear_color[2],
+                    clear_color[3],
+                );
+                if let Err(err) = window.painter.try_paint_primitives(
+                    &window.display,
+                    &mut target,
+                    pixels_per_point,
+                    &clipped_primitives,
+                ) {
+                    log::error!("Failed to paint viewport {viewport_id:?}: {err}");
+                }
+
+                match viewport.actions_requested.handle_screenshots(
+                    &mut window.egui_winit,
+                    viewport_id,
+                    &window.painter,
+                    &window.display,
+                    &target,
+                ) {
+                    Ok(true) => self.egui_ctx.request_repaint_of(viewport_id),
+                    Ok(false) => {}
+                    Err(err) => {
+                        log::error!("Failed to take screenshot of viewport {viewport_id:?}: {err}");
+                    }
+                }
+
+                if let Err(err) = target.finish() {
+                    log::error!("Failed to swap buffers of viewport {viewport_id:?}: {err}");
+                }
+            }
+        }
+
+        for &id in &textures_delta.free {
+            self.free_texture(id);
+        }
+
+        self.handle_viewport_output(viewport_output);
+    }
+
+    fn handle_viewport_output(
+        &mut self,
+        viewport_output: std::collections::BTreeMap<ViewportId, ViewportOutput>,
+    ) {
+        let active_viewports: ViewportIdSet = viewport_output.keys().copied().collect();
+
+        for (viewport_id, output) in viewport_output {
+            let ViewportOutput {
+                parent,
+                class,
+                builder,
+                viewport_ui_cb,
+                commands,
+                repaint_delay,
+            } = output;
+
+            let ids = ViewportIdPair::from_self_and_parent(viewport_id, parent);
+            let egui_ctx = self.egui_ctx.clone();
+            let viewport = self.initialize_or_update_viewport(ids, class, builder, viewport_ui_cb);
+
+            if let Some(window) = &viewport.window {
+                viewport.actions_requested.process_viewport_commands(
+                    &egui_ctx,
+                    &mut viewport.info,
+                    commands,
+                    &window.window,
+                );
+            }
+
+            // A `ViewportCommand::Close` only takes effect if it isn't canceled on the next frame:
+            let repaint_delay = if viewport.info.close_requested() {
+                Duration::ZERO
+            } else {
+                repaint_delay
+            };
+
+            // Immediate viewports are repainted by their parent:
+            let repaint_id = if class == ViewportClass::Immediate {
+                parent
+            } else {
+                viewport_id
+            };
+            if let Some(repaint_at) = Instant::now().checked_add(repaint_delay) {
+                if let Some(viewport) = self.viewports.get_mut(&repaint_id) {
+                    viewport.repaint_at = Some(
+                        viewport
+                            .repaint_at
+                            .map_or(repaint_at, |t: Instant| t.min(repaint_at)),
+                    );
+                }
+            }
+        }
+
+        self.viewports
+            .retain(|id, _| *id == ViewportId::ROOT || active_viewports.contains(id));
+        let viewports = &self.viewports;
+        self.viewport_from_window
+            .retain(|_, id| viewports.contains_key(id));
+    }
+
+    /// Upload the texture to every window, and remember it for windows opened later.
+    fn set_texture(&mut self, id: egui::TextureId, image_delta: &egui::epaint::ImageDelta) {
+        for (viewport_id, viewport) in &mut self.viewports {
+            if let Some(window) = &mut viewport.window {
+                if let Err(err) = window
+                    .painter
+                    .try_set_texture(&window.display, id, image_delta)
+                {
+                    log::error!(
+                        "Failed to upload texture {id:?} to viewport {viewport_id:?}: {err}"
+                    );
+                }
+            }
+        }
+
+        self.textures.set(id, image_delta);
+    }
+
+    fn free_texture(&mut self, id: egui::TextureId) {
+        for viewport in self.viewports.values_mut() {
+            if let Some(window) = &mut viewport.window {
+                window.painter.free_texture(id);
+            }
+        }
+        self.textures.free(id);
+    }
+}
+
+fn render_immediate_viewport(
+    egui_ctx: &egui::Context,
+    shared: &RefCell<SharedState>,
+    immediate_viewport: ImmediateViewport<'_>,
+) {
+    let ImmediateViewport {
+        ids,
+        builder,
+        mut viewport_ui_cb,
+    } = immediate_viewport;
+
+    let raw_input = {
+        let mut shared = shared.borrow_mut();
+        shared.initialize_or_update_viewport(ids, ViewportClass::Immediate, builder, None);
+
+        // Without a window we still have to run the ui, but nothing gets painted.
+        // The window is created once we are back in the event loop.
+        shared
+            .take_egui_input(ids.this)
+            .unwrap_or_else(|| egui::RawInput {
+                viewport_id: ids.this,
+                viewports: shared.viewport_infos(),
+                ..Default::default()
+            })
+    };
+
+    // No borrow of the shared state may be held here, since nested immediate viewports need it:
+    let full_output = egui_ctx.run(raw_input, |egui_ctx| viewport_ui_cb(egui_ctx));
+
+    shared.borrow_mut().handle_output(ids.this, full_output);
+}
@@ -0,0 +1,249 @@
+//! Snapshot a UI without a window, the way a CI machine without a GPU would,
+//! e.g. with Mesa's `llvmpipe` behind a surfaceless EGL display.
+//!
+//! Skipped when no such display is available.
+
+use std::{ffi::CString, rc::Rc, sync::Arc};
+
+use glium::glutin::{
+    api::egl,
+    config::{ConfigSurfaceTypes, ConfigTemplateBuilder},
+    context::ContextAttributesBuilder,
+    display::GlDisplay as _,
+    prelude::PossiblyCurrentGlContext as _,
+};
+
+/// A glium backend for an EGL context without any surface to draw to.
+///
+/// egui is painted into a texture instead.
+struct SurfacelessBackend {
+    display: egl::display::Display,
+    context: egl::context::PossiblyCurrentContext,
+}
+
+unsafe impl glium::backend::Backend for SurfacelessBackend {
+    fn swap_buffers(&self) -> Result<(), glium::SwapBuffersError> {
+        Ok(())
+    }
+
+    unsafe fn get_proc_address(&self, symbol: &str) -> *const std::ffi::c_void {
+        let symbol = CString::new(symbol).unwrap();
+        self.display.get_proc_address(&symbol)
+    }
+
+    fn get_framebuffer_dimensions(&self) -> (u32, u32) {
+        (0, 0)
+    }
+
+    fn resize(&self, _new_size: (u32, u32)) {}
+
+    fn is_current(&self) -> bool {
+        self.context.is_current()
+    }
+
+    unsafe fn make_current(&self) {
+        if let Err(err) = self.context.make_current_surfaceless() {
+            panic!("Failed to make the context current: {err}");
+        }
+    }
+}
+
+/// `None` if there is no EGL device we can create a surfaceless context on.
+fn headless_facade() -> Option<Rc<glium::backend::Context>> {
+    let device = egl::device::Device::query_devices().ok()?.next()?;
+
+    // SAFETY: we pass no raw display handle.
+    let display = unsafe { egl::display::Display::with_device(&device, None) }.ok()?;
+
+    let template = ConfigTemplateBuilder::new()
+        .with_surface_type(ConfigSurfaceTypes::empty())
+        .build();
+    // SAFETY: the display is valid for as long as the configs are used.
+    let config = unsafe { display.find_configs(template) }.ok()?.next()?;
+
+    let context_attributes = ContextAttributesBuilder::new().build(None);
+    // SAFETY: the context is created without a window.
+    let context = unsafe { display.create_context(&config, &context_attributes) }
+        .ok()?
+        .make_current_surfaceless()
+        .ok()?;
+
+    let backend = SurfacelessBackend { display, context };
+    // SAFETY: the context was just created, so glium's state cache matches it.
+    unsafe { glium::backend::Context::new(backend, true, Default::default()) }.ok()
+}
+
+#[test]
+fn snapshot_of_filled_panel() {
+    let Some(facade) = headless_facade() else {
+        eprintln!("Skipping: no headless OpenGL context available");
+        return;
+    };
+
+    let fill = egui::Color32::from_rgb(200, 100, 50);
+    let image = egui_glium::snapshot(&facade, egui::vec2(40.0, 30.0), 2.0, |ctx| {
+        egui::CentralPanel::default()
+            .frame(egui::Frame::new().fill(fill))
+            .show(ctx, |ui| {
+                ui.label("Hello");
+            });
+    })
+    .unwrap();
+
+    assert_eq!(image.size, [80, 60]);
+    // The bottom right corner is far from the text:
+    assert_eq!(image[(75, 55)], fill);
+}
+
+/// Rendering code that knows nothing about egui.
+fn clear_rect(target: &mut impl glium::Surface, rect: &glium::Rect, color: (f32, f32, f32, f32)) {
+    target.clear(Some(rect), Some(color), false, None, None);
+}
+
+#[test]
+fn paint_callback_gets_a_glium_surface() {
+    let Some(facade) = headless_facade() else {
+        eprintln!("Skipping: no headless OpenGL context available");
+        return;
+    };
+
+    let image = egui_glium::snapshot(&facade, egui::vec2(40.0, 30.0), 1.0, |ctx| {
+        egui::CentralPanel::default()
+            .frame(egui::Frame::new())
+            .show(ctx, |ui| {
+                let rect =
+                    egui::Rect::from_min_size(egui::pos2(10.0, 10.0), egui::vec2(10.0, 10.0));
+                ui.painter().add(egui::PaintCallback {
+                    rect,
+                    callback: Arc::new(egui_glium::CallbackFn::new(|_info, ctx| {
+                        use glium::Surface as _;
+
+                        assert_eq!(ctx.surface.get_dimensions(), (40, 30));
+                        clear_rect(&mut ctx.surface, &ctx.viewport, (0.0, 1.0, 0.0, 1.0));
+                    })),
+                });
+            });
+    })
+    .unwrap();
+
+    assert_eq!(image[(15, 15)], egui::Color32::GREEN);
+    assert_eq!(image[(5, 5)], egui::Color32::TRANSPARENT);
+}
+
+/// Allow for the rounding of the GPU, which may differ from ours.
+fn assert_color_near(actual: egui::Color32, expected: egui::Color32) {
+    let near = actual
+        .to_array()
+        .iter()
+        .zip(expected.to_array())
+        .all(|(&a, e)| a.abs_diff(e) <= 1);
+    assert!(near, "Expected {expected:?}, got {actual:?}");
+}
+
+/// Show each of `textures` in a 10×10 square, left to right.
+fn snapshot_of_textures(
+    painter: &mut egui_glium::Painter,
+    facade: &Rc<glium::backend::Context>,
+    textures: &[egui::TextureId],
+) -> egui::ColorImage {
+    let size = egui::vec2(10.0 * textures.len() as f32, 10.0);
+    egui_glium::snapshot_with_painter(painter, facade, size, 1.0, |ctx| {
+        egui::CentralPanel::default()
+            .frame(egui::Frame::new())
+            .show(ctx, |ui| {
+                for (i, &texture_id) in textures.iter().enumerate() {
+                    let rect = egui::Rect::from_min_size(
+                        egui::pos2(10.0 * i as f32, 0.0),
+                        egui::vec2(10.0, 10.0),
+                    );
+                    let uv = egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0));
+                    ui.painter()
+                        .image(texture_id, rect, uv, egui::Color32::WHITE);
+                }
+            });
+    })
+    .unwrap()
+}
+
+#[test]
+fn native_texture_kinds() {
+    use egui_glium::NativeTexture;
+    use glium::texture::{SrgbTexture2d, Texture2d};
+
+    let Some(facade) = headless_facade() else {
+        eprintln!("Skipping: no headless OpenGL context available");
+        return;
+    };
+    let mut painter = egui_glium::Painter::try_new(&facade).unwrap();
+
+    let textures = [
+        NativeTexture::Srgb(Rc::new(
+            SrgbTexture2d::new(&facade, vec![vec![(200_u8, 100_u8, 50_u8, 255_u8)]]).unwrap(),
+        )),
+        NativeTexture::Linear(Rc::new(
+            Texture2d::new(&facade, vec![vec![(0.5_f32, 0.5_f32, 0.5_f32, 1.0_f32)]]).unwrap(),
+        )),
+        NativeTexture::Gray(Rc::new(
+            Texture2d::new(&facade, vec![vec![0.5_f32]]).unwrap(),
+        )),
+        NativeTexture::Alpha(Rc::new(
+            Texture2d::new(&facade, vec![vec![0.5_f32]]).unwrap(),
+        )),
+    ];
+    let ids: Vec<_> = textures
+        .into_iter()
+        .map(|texture| painter.register_native_texture(texture, Default::default()))
+        .collect();
+
+    let image = snapshot_of_textures(&mut painter, &facade, &ids);
+
+    let half_in_gamma = egui::ecolor::gamma_u8_from_linear_f32(0.5);
+    let expected = [
+        egui::Color32::from_rgb(200, 100, 50),
+        egui::Color32::from_gray(half_in_gamma),
+        egui::Color32::from_gray(half_in_gamma),
+        egui::Color32::from_rgba_premultiplied(128, 128, 128, 128),
+    ];
+    for (i, expected) in expected.into_iter().enumerate() {
+        assert_color_near(image[(10 * i + 5, 5)], expected);
+    }
+}
+
+#[test]
+fn hdr_texture_is_tone_mapped() {
+    use egui_glium::{HdrOptions, NativeTexture, ToneMapping};
+    use glium::texture::{MipmapsOption, Texture2d, UncompressedFloatFormat};
+
+    let Some(facade) = headless_facade() else {
+        eprintln!("Skipping: no headless OpenGL context available");
+        return;
+    };
+    let mut painter = egui_glium::Painter::try_new(&facade).unwrap();
+
+    // A color of 4.0 at half coverage, with premultiplied alpha:
+    let texture = Texture2d::with_format(
+        &facade,
+        vec![vec![(2.0_f32, 2.0_f32, 2.0_f32, 0.5_f32)]],
+        UncompressedFloatFormat::F16F16F16F16,
+        MipmapsOption::NoMipmap,
+    )
+    .unwrap();
+    let id =
+        painter.register_native_texture(NativeTexture::Hdr(Rc::new(texture)), Default::default());
+    painter.set_hdr_options(
+        id,
+        HdrOptions {
+            exposure: 0.0,
+            tone_mapping: ToneMapping::Reinhard,
+        },
+    );
+
+    let image = snapshot_of_textures(&mut painter, &facade, &[id]);
+
+    // Reinhard maps 4.0 to 4 / (1 + 4) = 0.8, which is then premultiplied again:
+    let value = (0.5 * egui::ecolor::gamma_from_linear(0.8) * 255.0).round() as u8;
+    assert_color_near(
+        image[(5, 5)],
+        egui::Color32::from_rgba_premultiplied(value, value, value, 128),
+    );
+}